@@ -19,3 +19,9 @@ fi
 
 # Create the symlink
 sudo ln -s "$target" "$symlink_path"
+
+# Install the man pages
+man_dir="/usr/local/share/man/man1"
+"$target" man --out-dir "$current_dir/target/man" > /dev/null
+sudo mkdir -p "$man_dir"
+sudo cp "$current_dir"/target/man/*.1 "$man_dir"
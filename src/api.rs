@@ -1,7 +1,15 @@
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
 use ureq::json;
 
 use crate::utils::{self, Due};
 
+/// Requests sent at once by the commands working on many tasks, there's no bulk endpoint.
+pub const MAX_PARALLEL_REQUESTS: usize = 8;
+
 struct Token(String);
 
 // internal
@@ -10,30 +18,16 @@ struct Token(String);
 // prod
 const API_BASE_PATH: &str = "https://rsm.fefe.tech/api/";
 
-#[derive(Default)]
 pub struct Api {
     token: Option<Token>,
+    // shared so connections are reused across requests
+    agent: ureq::Agent,
 }
 
 impl Api {
     // START API UTILS
     pub fn from_token_file() -> Self {
-        #[cfg(target_os = "linux")]
-        let exe_path = std::env::current_exe().ok().unwrap(); // Get the current executable's path
-
-        #[cfg(target_os = "macos")]
-        let exe_path = std::env::current_exe()
-            .ok()
-            .and_then(|p| std::fs::canonicalize(p).ok())
-            .unwrap();
-
-        // Go to the parent directory (where the executable is located) from /target/release
-        let token_path: std::path::PathBuf = exe_path
-            .parent()
-            .and_then(|p| p.parent())
-            .and_then(|p| p.parent())
-            .map(|p| p.join(".token"))
-            .unwrap();
+        let token_path = utils::data_path(".token");
 
         let token: Option<Token> = std::fs::read_to_string(token_path)
             .ok()
@@ -43,20 +37,35 @@ impl Api {
                 Token(token)
             });
 
-        Api { token }
+        Api {
+            token,
+            // keeps a connection per parallel request
+            agent: ureq::AgentBuilder::new()
+                .max_idle_connections_per_host(MAX_PARALLEL_REQUESTS)
+                .build(),
+        }
     }
 
     pub fn has_token(&self) -> bool {
         self.token.is_some()
     }
 
+    /// Identifies the logged in user without keeping the token around, e.g. in a cache.
+    pub fn user_key(&self) -> Option<u64> {
+        self.token.as_ref().map(|token| {
+            let mut hasher = DefaultHasher::new();
+            token.0.hash(&mut hasher);
+            hasher.finish()
+        })
+    }
+
     pub fn has_connection(&self) -> Result<bool, String> {
         use std::error::Error;
         use std::io;
 
         let url = "http://neverssl.com";
 
-        match ureq::get(url).call() {
+        match self.agent.get(url).call() {
             Ok(_) => Ok(true),
             Err(ureq::Error::Transport(transport_error)) => {
                 if let Some(io_err) = transport_error
@@ -115,7 +124,7 @@ impl Api {
     ) -> Result<serde_json::Value, String> {
         let url = format!("{API_BASE_PATH}signup");
 
-        let request = ureq::post(&url);
+        let request = self.agent.post(&url);
 
         // defaults to UTC
         let tz: chrono_tz::Tz = utils::get_sys_tz().unwrap_or_default();
@@ -127,7 +136,7 @@ impl Api {
     pub fn login(&self, usr: String, pwd: String) -> Result<serde_json::Value, String> {
         let url = format!("{API_BASE_PATH}login");
 
-        let request = ureq::post(&url);
+        let request = self.agent.post(&url);
         let json_body = json!({"username": usr, "password": pwd});
 
         Self::handle_response(request.send_json(json_body))
@@ -136,7 +145,7 @@ impl Api {
     pub fn logout(&self, logout: bool) -> Result<serde_json::Value, String> {
         let url = format!("{API_BASE_PATH}logout");
 
-        let request = ureq::post(&url);
+        let request = self.agent.post(&url);
         let json_body = json!({"logout": logout});
 
         let request = if let Some(token) = &self.token {
@@ -158,7 +167,7 @@ impl Api {
     ) -> Result<serde_json::Value, String> {
         let url = format!("{API_BASE_PATH}table/{tablename}");
 
-        let request = ureq::post(&url);
+        let request = self.agent.post(&url);
         let request = if let Some(token) = &self.token {
             request.set("Authorization", &format!("Bearer {}", token.0))
         } else {
@@ -173,7 +182,7 @@ impl Api {
     pub fn drop_table(&self, tablename: &str) -> Result<serde_json::Value, String> {
         let url = format!("{API_BASE_PATH}table/{tablename}");
 
-        let request = ureq::delete(&url);
+        let request = self.agent.delete(&url);
         let request = if let Some(token) = &self.token {
             request.set("Authorization", &format!("Bearer {}", token.0))
         } else {
@@ -186,7 +195,7 @@ impl Api {
     pub fn list_tables_specs(&self) -> Result<serde_json::Value, String> {
         let url = format!("{API_BASE_PATH}table/list");
 
-        let request = ureq::get(&url);
+        let request = self.agent.get(&url);
         let request = if let Some(token) = &self.token {
             request.set("Authorization", &format!("Bearer {}", token.0))
         } else {
@@ -218,7 +227,7 @@ impl Api {
             url.push_str(&query_params.join("&"));
         }
 
-        let request = ureq::get(&url);
+        let request = self.agent.get(&url);
         let request = if let Some(token) = &self.token {
             request.set("Authorization", &format!("Bearer {}", token.0))
         } else {
@@ -237,7 +246,7 @@ impl Api {
     ) -> Result<serde_json::Value, String> {
         let url = format!("{API_BASE_PATH}{tablename}");
 
-        let request = ureq::post(&url);
+        let request = self.agent.post(&url);
         let json_body = json!({"description": task,"due": due, "group": group});
 
         let request = if let Some(token) = &self.token {
@@ -252,7 +261,7 @@ impl Api {
     pub fn remove_task(&self, tablename: &str, id: usize) -> Result<serde_json::Value, String> {
         let url = format!("{API_BASE_PATH}{tablename}/{id}");
 
-        let request = ureq::delete(&url);
+        let request = self.agent.delete(&url);
 
         let request = if let Some(token) = &self.token {
             request.set("Authorization", &format!("Bearer {}", token.0))
@@ -266,14 +275,14 @@ impl Api {
     pub fn update_task(
         &self,
         tablename: &str,
-        id: &str,
+        id: usize,
         task: Option<&str>,
         due: Option<Due>,
         group: Option<&str>,
     ) -> Result<serde_json::Value, String> {
         let url = format!("{API_BASE_PATH}{tablename}/{id}");
 
-        let request = ureq::put(&url);
+        let request = self.agent.put(&url);
         let json_body = json!({"description": task,"due": due, "group": group});
 
         let request = if let Some(token) = &self.token {
@@ -288,7 +297,7 @@ impl Api {
     pub fn clear_table(&self, tablename: &str) -> Result<serde_json::Value, String> {
         let url = format!("{API_BASE_PATH}{tablename}/clear");
 
-        let request = ureq::delete(&url);
+        let request = self.agent.delete(&url);
 
         let request = if let Some(token) = &self.token {
             request.set("Authorization", &format!("Bearer {}", token.0))
@@ -300,3 +309,44 @@ impl Api {
     }
     // END TASK METHODS
 }
+
+/// Calls `f` on every item from up to `workers` threads, e.g. to send one request per task.
+///
+/// The results are in the order of `items`, whatever order the requests finished in.
+pub fn parallel<T: Sync, R: Send>(
+    items: &[T],
+    workers: usize,
+    f: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+
+    let mut results: Vec<(usize, R)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers.clamp(1, items.len().max(1)))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = vec![];
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(i) else {
+                            break;
+                        };
+                        results.push((i, f(item)));
+                    }
+                    results
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+            .collect()
+    });
+
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, result)| result).collect()
+}
@@ -0,0 +1,82 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::Api,
+    utils::{self, TableSpec},
+};
+
+const SPECS_CACHE_FILE: &str = ".specs_cache";
+// tables rarely change, and every command that changes them refreshes the cache
+const SPECS_CACHE_TTL_MINUTES: i64 = 10;
+
+#[derive(Serialize, Deserialize)]
+struct SpecsCache {
+    // the specs of another account mustn't be served after switching tokens
+    #[serde(default)]
+    user: Option<u64>,
+    fetched_at: chrono::DateTime<chrono::Local>,
+    specs: Vec<TableSpec>,
+}
+
+/// Fetches the specs of every table from the api, and refreshes the cache with them.
+pub fn fetch_table_specs(api: &Api) -> Result<Vec<TableSpec>, String> {
+    let res = api.list_tables_specs()?;
+
+    let specs: Vec<TableSpec> = res
+        .get("res")
+        .and_then(|v| v.as_array())
+        .map(|arr| arr.iter().map(TableSpec::from_json).collect())
+        .unwrap_or_default();
+
+    let cache = SpecsCache {
+        user: api.user_key(),
+        fetched_at: chrono::Local::now(),
+        specs: specs.clone(),
+    };
+    // the cache is only an optimization, failing to write it isn't an error
+    if let Ok(content) = serde_json::to_string(&cache) {
+        let _ = fs::write(utils::data_path(SPECS_CACHE_FILE), content);
+    }
+
+    Ok(specs)
+}
+
+fn cached_specs(api: &Api) -> Option<Vec<TableSpec>> {
+    let content = fs::read_to_string(utils::data_path(SPECS_CACHE_FILE)).ok()?;
+    let cache: SpecsCache = serde_json::from_str(&content).ok()?;
+    if cache.user.is_none() || cache.user != api.user_key() {
+        return None;
+    }
+
+    let age = chrono::Local::now() - cache.fetched_at;
+    (age < chrono::Duration::minutes(SPECS_CACHE_TTL_MINUTES)).then_some(cache.specs)
+}
+
+/// Specs of every table, from the cache when it's fresh enough.
+pub fn table_specs(api: &Api) -> Result<Vec<TableSpec>, String> {
+    match cached_specs(api) {
+        Some(specs) => Ok(specs),
+        None => fetch_table_specs(api),
+    }
+}
+
+/// Specs of a single table, from the cache when possible.
+///
+/// A table missing from the cache may have been created since, so the cache
+/// is refreshed once before giving up.
+pub fn table_spec(api: &Api, tablename: &str) -> Result<TableSpec, String> {
+    let find = |specs: Vec<TableSpec>| specs.into_iter().find(|spec| spec.name == tablename);
+
+    if let Some(spec) = cached_specs(api).and_then(find) {
+        return Ok(spec);
+    }
+
+    find(fetch_table_specs(api)?).ok_or_else(|| format!("Table '{tablename}' not found"))
+}
+
+/// Drops the cache, to be called after the tables changed.
+pub fn invalidate() {
+    let _ = fs::remove_file(utils::data_path(SPECS_CACHE_FILE));
+}
@@ -0,0 +1,398 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Weekday};
+
+use serde::Deserialize;
+
+use crate::utils::{parse_due, Due};
+
+// time of a due given as a day only, e.g. 'tomorrow'
+const DEFAULT_HOUR: u32 = 9;
+// longest date looked for at the end of the text or after 'due:', e.g. 'on next friday 10:00'
+const MAX_DATE_WORDS: usize = 4;
+
+/// The fields found in a quick-capture text, e.g. `call bob tomorrow 10:00 #calls !high`.
+///
+/// The api has no field for tags and priorities, they're only shown in the preview.
+#[derive(Debug, Default)]
+pub struct Capture {
+    pub description: String,
+    pub due: Option<Due>,
+    pub group: Option<String>,
+    pub tags: Vec<String>,
+    pub priority: Option<String>,
+}
+
+/// Extracts `#group`, `@tag`, `!priority`, `due:<date>` and a trailing date from `text`.
+pub fn parse_capture(text: &str, now: NaiveDateTime) -> Result<Capture, String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let mut capture = Capture::default();
+    let mut kept: Vec<&str> = vec![];
+
+    let mut i = 0;
+    while i < words.len() {
+        let word = words[i];
+        i += 1;
+
+        // '#123' is more likely a reference than a group, e.g. 'review PR #123'
+        if let Some(group) = word
+            .strip_prefix('#')
+            .filter(|group| group.starts_with(char::is_alphabetic))
+        {
+            if let Some(previous) = &capture.group {
+                return Err(format!(
+                    "Only one group can be given, found '#{previous}' and '#{group}'"
+                ));
+            }
+            capture.group = Some(group.to_string());
+        } else if let Some(value) = word.strip_prefix("due:") {
+            if capture.due.is_some() {
+                return Err("Only one 'due:' can be given".to_string());
+            }
+
+            // the date can go on in the next words, e.g. 'due:2026-10-20 10:00' or 'due:in 2 days'
+            let mut date = vec![value];
+            date.extend(words.iter().skip(i).take(MAX_DATE_WORDS - 1));
+            let (due, used) = (1..=date.len())
+                .rev()
+                .find_map(|n| parse_date(&date[..n], now).map(|due| (due, n)))
+                .ok_or_else(|| format!("Invalid due '{word}'"))?;
+
+            capture.due = Some(Due(due));
+            i += used - 1;
+        } else if let Some(tag) = word.strip_prefix('@').filter(|tag| !tag.is_empty()) {
+            capture.tags.push(tag.to_string());
+        } else if let Some(priority) = word.strip_prefix('!').filter(|p| is_priority(p)) {
+            if let Some(previous) = &capture.priority {
+                return Err(format!(
+                    "Only one priority can be given, found '!{previous}' and '!{priority}'"
+                ));
+            }
+            capture.priority = Some(priority.to_string());
+        } else {
+            kept.push(word);
+        }
+    }
+
+    // a date at the end, the tags and priority are already out, e.g. '... tomorrow 10:00 !high'
+    if capture.due.is_none() {
+        let end = kept.len();
+
+        // at least a word of description is left
+        for n in (1..=MAX_DATE_WORDS.min(end.saturating_sub(1))).rev() {
+            let date = &kept[end - n..end];
+            if let Some(due) = parse_date(date, now).filter(|_| !is_short_weekday(date)) {
+                capture.due = Some(Due(due));
+                kept.drain(end - n..end);
+                break;
+            }
+        }
+    }
+
+    capture.description = kept.join(" ");
+    if capture.description.is_empty() {
+        return Err("The description can't be empty".to_string());
+    }
+
+    Ok(capture)
+}
+
+/// A weekday shortened and alone, e.g. 'sun' or 'wed', which is more likely a word
+/// at the end of a text than a date.
+fn is_short_weekday(words: &[&str]) -> bool {
+    match words {
+        [word] => {
+            let word = word.to_lowercase();
+            parse_weekday(&word).is_some() && !word.ends_with("day")
+        }
+        _ => false,
+    }
+}
+
+fn is_priority(word: &str) -> bool {
+    !word.is_empty() && word.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// Parses a date written in words: `tomorrow`, `friday 10:00`, `at 3pm`, `in 2 days`, `2026-10-20`...
+///
+/// A day without a time is at 9:00, a time without a day is today, or tomorrow
+/// if that time is already past.
+fn parse_date(words: &[&str], now: NaiveDateTime) -> Option<NaiveDateTime> {
+    let words: Vec<String> = words.iter().map(|word| word.to_lowercase()).collect();
+    let mut words: Vec<&str> = words.iter().map(String::as_str).collect();
+
+    // a leading preposition belongs to the date, e.g. 'call bob at 10:00'
+    if matches!(words.first(), Some(&("at" | "on" | "by"))) && words.len() > 1 {
+        words.remove(0);
+    }
+
+    if words.first() == Some(&"in") {
+        let duration = parse_duration(&words[1..])?;
+        let due = now + duration;
+        return due.with_second(0).and_then(|due| due.with_nanosecond(0));
+    }
+
+    // the time is either last, possibly after 'at', or first
+    let (day, time) = match words.as_slice() {
+        [day @ .., "at", time] | [day @ .., time] if parse_time(time).is_some() => {
+            (day.to_vec(), parse_time(time))
+        }
+        [time, day @ ..] if parse_time(time).is_some() => (day.to_vec(), parse_time(time)),
+        day => (day.to_vec(), None),
+    };
+
+    if day.is_empty() {
+        let time = time?;
+        let today = now.date().and_time(time);
+        return Some(if today < now {
+            today + Duration::days(1)
+        } else {
+            today
+        });
+    }
+
+    let date = parse_day(&day, now.date())?;
+    let time = time.or_else(|| NaiveTime::from_hms_opt(DEFAULT_HOUR, 0, 0))?;
+
+    Some(date.and_time(time))
+}
+
+fn parse_day(words: &[&str], today: NaiveDate) -> Option<NaiveDate> {
+    match words {
+        ["today" | "tonight"] => Some(today),
+        ["tomorrow" | "tmr" | "tmrw"] => today.succ_opt(),
+        [date] if date.contains('-') => NaiveDate::parse_from_str(date, "%Y-%m-%d").ok(),
+        // the coming one, never today
+        [weekday] | ["next", weekday] => {
+            let weekday = parse_weekday(weekday)?;
+            let days =
+                (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+            Some(today + Duration::days(if days == 0 { 7 } else { days as i64 }))
+        }
+        _ => None,
+    }
+}
+
+fn parse_weekday(word: &str) -> Option<Weekday> {
+    let weekday = match word {
+        "mon" | "monday" => Weekday::Mon,
+        "tue" | "tues" | "tuesday" => Weekday::Tue,
+        "wed" | "wednesday" => Weekday::Wed,
+        "thu" | "thurs" | "thursday" => Weekday::Thu,
+        "fri" | "friday" => Weekday::Fri,
+        "sat" | "saturday" => Weekday::Sat,
+        "sun" | "sunday" => Weekday::Sun,
+        _ => return None,
+    };
+
+    Some(weekday)
+}
+
+/// Parses `10:00`, `9:30pm`, `3pm` or `noon`.
+fn parse_time(word: &str) -> Option<NaiveTime> {
+    if word == "noon" {
+        return NaiveTime::from_hms_opt(12, 0, 0);
+    }
+    if let Ok(time) = NaiveTime::parse_from_str(word, "%H:%M") {
+        return Some(time);
+    }
+
+    let (clock, pm) = if let Some(clock) = word.strip_suffix("am") {
+        (clock, false)
+    } else {
+        (word.strip_suffix("pm")?, true)
+    };
+    let (hour, minute) = clock.split_once(':').unwrap_or((clock, "0"));
+    let (hour, minute): (u32, u32) = (hour.parse().ok()?, minute.parse().ok()?);
+
+    if !(1..=12).contains(&hour) {
+        return None;
+    }
+    NaiveTime::from_hms_opt(hour % 12 + if pm { 12 } else { 0 }, minute, 0)
+}
+
+/// Parses `2 days`, `3h` or `30 minutes`.
+fn parse_duration(words: &[&str]) -> Option<Duration> {
+    let (amount, unit) = match words {
+        [amount, unit] => (*amount, *unit),
+        [word] => {
+            let split = word.find(|c: char| !c.is_ascii_digit())?;
+            word.split_at(split)
+        }
+        _ => return None,
+    };
+    let amount: i64 = amount.parse().ok()?;
+
+    match unit {
+        "m" | "min" | "mins" | "minute" | "minutes" => Some(Duration::minutes(amount)),
+        "h" | "hour" | "hours" => Some(Duration::hours(amount)),
+        "d" | "day" | "days" => Some(Duration::days(amount)),
+        "w" | "week" | "weeks" => Some(Duration::weeks(amount)),
+        _ => None,
+    }
+}
+
+/// A task of a JSON line, e.g. `{"description": "call bob", "due": "2026-10-20 10:00"}`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonTask {
+    description: String,
+    due: Option<String>,
+    group: Option<String>,
+}
+
+/// Parses the tasks of a batch, one per line, either a JSON object or a quick-capture text.
+///
+/// Blank lines are skipped, every other line comes with its number so errors can point to it.
+pub fn parse_lines(
+    content: &str,
+    raw: bool,
+    now: NaiveDateTime,
+) -> Vec<(usize, Result<Capture, String>)> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| (i + 1, parse_line(line.trim(), raw, now)))
+        .collect()
+}
+
+fn parse_line(line: &str, raw: bool, now: NaiveDateTime) -> Result<Capture, String> {
+    if !line.starts_with('{') {
+        return if raw {
+            Ok(Capture {
+                description: line.to_string(),
+                ..Default::default()
+            })
+        } else {
+            parse_capture(line, now)
+        };
+    }
+
+    let task: JsonTask = serde_json::from_str(line).map_err(|e| format!("Invalid JSON: {e}"))?;
+    if task.description.trim().is_empty() {
+        return Err("The description can't be empty".to_string());
+    }
+
+    Ok(Capture {
+        description: task.description,
+        due: task.due.as_deref().map(parse_due).transpose()?,
+        group: task.group,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a monday
+    fn now() -> NaiveDateTime {
+        at(2026, 10, 19, 14, 30)
+    }
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .and_then(|date| date.and_hms_opt(hour, minute, 0))
+            .unwrap()
+    }
+
+    fn date(text: &str) -> Option<NaiveDateTime> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+        parse_date(&words, now())
+    }
+
+    #[test]
+    fn weekdays_are_the_coming_ones() {
+        assert_eq!(date("tuesday"), Some(at(2026, 10, 20, 9, 0)));
+        assert_eq!(date("sun 18:00"), Some(at(2026, 10, 25, 18, 0)));
+        // today's weekday wraps to the next week
+        assert_eq!(date("monday"), Some(at(2026, 10, 26, 9, 0)));
+        assert_eq!(date("next mon at 10:00"), Some(at(2026, 10, 26, 10, 0)));
+    }
+
+    #[test]
+    fn past_times_roll_to_tomorrow() {
+        assert_eq!(date("15:00"), Some(at(2026, 10, 19, 15, 0)));
+        assert_eq!(date("at 9am"), Some(at(2026, 10, 20, 9, 0)));
+        assert_eq!(date("noon"), Some(at(2026, 10, 20, 12, 0)));
+        // a time with a day is taken as is
+        assert_eq!(date("today 8:00"), Some(at(2026, 10, 19, 8, 0)));
+    }
+
+    #[test]
+    fn twelve_am_and_pm() {
+        assert_eq!(date("12am"), Some(at(2026, 10, 20, 0, 0)));
+        assert_eq!(date("12pm"), Some(at(2026, 10, 20, 12, 0)));
+        assert_eq!(date("tomorrow 12:30pm"), Some(at(2026, 10, 20, 12, 30)));
+        assert_eq!(date("13pm"), None);
+        assert_eq!(date("0am"), None);
+    }
+
+    #[test]
+    fn durations_are_from_now() {
+        assert_eq!(date("in 3h"), Some(at(2026, 10, 19, 17, 30)));
+        assert_eq!(date("in 2 days"), Some(at(2026, 10, 21, 14, 30)));
+        assert_eq!(date("in 45 minutes"), Some(at(2026, 10, 19, 15, 15)));
+        assert_eq!(date("in 3 fortnights"), None);
+    }
+
+    #[test]
+    fn fields_are_taken_out_of_the_description() {
+        let capture = parse_capture("call bob tomorrow 10:00 #calls @phone !high", now()).unwrap();
+
+        assert_eq!(capture.description, "call bob");
+        assert_eq!(capture.due.map(|due| due.0), Some(at(2026, 10, 20, 10, 0)));
+        assert_eq!(capture.group.as_deref(), Some("calls"));
+        assert_eq!(capture.tags, ["phone"]);
+        assert_eq!(capture.priority.as_deref(), Some("high"));
+    }
+
+    #[test]
+    fn numbers_after_a_hash_stay_in_the_description() {
+        let capture = parse_capture("review PR #123 #work", now()).unwrap();
+
+        assert_eq!(capture.description, "review PR #123");
+        assert_eq!(capture.group.as_deref(), Some("work"));
+    }
+
+    #[test]
+    fn due_prefix_takes_the_following_words() {
+        let capture = parse_capture("renew passport due:in 2 days", now()).unwrap();
+        assert_eq!(capture.description, "renew passport");
+        assert_eq!(capture.due.map(|due| due.0), Some(at(2026, 10, 21, 14, 30)));
+
+        let capture = parse_capture("due:2026-11-02 10:00 dentist", now()).unwrap();
+        assert_eq!(capture.description, "dentist");
+        assert_eq!(capture.due.map(|due| due.0), Some(at(2026, 11, 2, 10, 0)));
+
+        assert!(parse_capture("pay due:someday", now()).is_err());
+    }
+
+    #[test]
+    fn a_date_alone_is_the_description() {
+        let capture = parse_capture("tomorrow", now()).unwrap();
+
+        assert_eq!(capture.description, "tomorrow");
+        assert!(capture.due.is_none());
+    }
+
+    #[test]
+    fn a_short_weekday_alone_is_a_word() {
+        for text in [
+            "walk in the sun",
+            "pack for the sat",
+            "plan the wed",
+            "see you mon",
+        ] {
+            let capture = parse_capture(text, now()).unwrap();
+            assert_eq!(capture.description, text);
+            assert!(capture.due.is_none());
+        }
+
+        // written in full, with a time, 'on' or 'next' it's a date
+        let due = |text| parse_capture(text, now()).unwrap().due.map(|due| due.0);
+        assert_eq!(due("walk on sunday"), Some(at(2026, 10, 25, 9, 0)));
+        assert_eq!(due("walk sun 18:00"), Some(at(2026, 10, 25, 18, 0)));
+        assert_eq!(due("walk on sun"), Some(at(2026, 10, 25, 9, 0)));
+        assert_eq!(due("walk next wed"), Some(at(2026, 10, 21, 9, 0)));
+    }
+}
@@ -0,0 +1,168 @@
+use std::io;
+
+use clap::CommandFactory;
+use clap_complete::{env::Shells, CompletionCandidate};
+
+use crate::{api::Api, cache, config::Config, utils};
+
+pub const SHELLS: [&str; 5] = ["bash", "zsh", "fish", "elvish", "powershell"];
+
+/// Writes the script that registers the dynamic completions of `bin` in `shell`.
+///
+/// The script calls back into this executable with `COMPLETE=<shell>` set,
+/// which is handled by `clap_complete::CompleteEnv` at the start of `main`.
+pub fn write_registration(shell: &str, name: &str, bin: &str) -> Result<(), String> {
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(shell)
+        .ok_or_else(|| format!("Unknown shell '{shell}'"))?;
+
+    let exe = std::env::current_exe().map_err(|e| format!("Internal error: {e}"))?;
+
+    completer
+        .write_registration(
+            "COMPLETE",
+            name,
+            bin,
+            &exe.to_string_lossy(),
+            &mut io::stdout(),
+        )
+        .map_err(|e| format!("Internal error: {e}"))
+}
+
+/// Whether `flag` is an option of `cmd` whose value is the next argument, to tell its
+/// value apart from the positionals.
+fn takes_value(cmd: &clap::Command, flag: &str) -> bool {
+    cmd.get_arguments()
+        .filter(|arg| arg.get_action().takes_values())
+        .any(|arg| match flag.strip_prefix("--") {
+            Some(long) => {
+                arg.get_long() == Some(long)
+                    || arg
+                        .get_all_aliases()
+                        .is_some_and(|aliases| aliases.contains(&long))
+            }
+            None => {
+                let mut short = flag.strip_prefix('-').unwrap_or_default().chars();
+                match (short.next(), short.next()) {
+                    (Some(c), None) => {
+                        arg.get_short() == Some(c)
+                            || arg
+                                .get_all_short_aliases()
+                                .is_some_and(|aliases| aliases.contains(&c))
+                    }
+                    _ => false,
+                }
+            }
+        })
+}
+
+/// Table named on the command line being completed, i.e. the first positional after the
+/// subcommand, or the default table.
+///
+/// The shell passes the words being completed after a `--`, the last one is
+/// the word under the cursor, so it's never considered.
+fn completing_tablename() -> Option<String> {
+    let args: Vec<String> = std::env::args()
+        .skip_while(|arg| arg != "--")
+        .skip(2) // the '--' and the binary name
+        .collect();
+    let args = &args[..args.len().saturating_sub(1)];
+
+    // the global options are only on the subcommands once built
+    let mut cmd = crate::Cli::command();
+    cmd.build();
+
+    let mut positionals = vec![];
+    let mut skip_next = false;
+    for arg in args {
+        if skip_next {
+            skip_next = false;
+        } else if takes_value(&cmd, arg) {
+            skip_next = true;
+        } else if arg.starts_with('-') {
+            continue;
+        } else if let Some(sub) = cmd.find_subcommand(arg).filter(|_| positionals.is_empty()) {
+            cmd = sub.clone();
+        } else {
+            positionals.push(arg.clone());
+        }
+    }
+
+    // without a table on the command line, the command goes to the default one,
+    // ids in place of the table also mean it was left out, as when the command runs
+    let config = Config::load().ok()?;
+    match positionals.into_iter().next() {
+        Some(tablename)
+            if config.default_table.is_none() || utils::parse_ids(&tablename).is_err() =>
+        {
+            Some(config.resolve(&tablename))
+        }
+        _ => config.default_table,
+    }
+}
+
+fn table_tasks(tablename: &str) -> Vec<serde_json::Value> {
+    Api::from_token_file()
+        .list_table_contents(tablename, None, None)
+        .ok()
+        .and_then(|res| res.get("res").and_then(|v| v.as_array()).cloned())
+        .unwrap_or_default()
+}
+
+/// Table names, from the cached table specs.
+pub fn tables() -> Vec<CompletionCandidate> {
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+
+    cache::table_specs(&Api::from_token_file())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|spec| {
+            let help = format!(
+                "due: {}, group: {}",
+                yes_no(spec.has_due),
+                yes_no(spec.has_group)
+            );
+            CompletionCandidate::new(spec.name).help(Some(help.into()))
+        })
+        .collect()
+}
+
+/// Groups used in the table being completed.
+pub fn groups() -> Vec<CompletionCandidate> {
+    let Some(tablename) = completing_tablename() else {
+        return vec![];
+    };
+
+    let mut groups: Vec<String> = table_tasks(&tablename)
+        .iter()
+        .filter_map(|task| task.get("group").and_then(|v| v.as_str()))
+        .map(str::to_string)
+        .collect();
+    groups.sort();
+    groups.dedup();
+
+    groups.into_iter().map(CompletionCandidate::new).collect()
+}
+
+/// Ids of the table being completed, with their description.
+pub fn ids() -> Vec<CompletionCandidate> {
+    let Some(tablename) = completing_tablename() else {
+        return vec![];
+    };
+
+    table_tasks(&tablename)
+        .iter()
+        .filter_map(|task| {
+            let id = task.get("id").and_then(|v| v.as_u64())?;
+            let description = task
+                .get("description")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default();
+
+            Some(
+                CompletionCandidate::new(id.to_string()).help(Some(description.to_string().into())),
+            )
+        })
+        .collect()
+}
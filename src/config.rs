@@ -0,0 +1,136 @@
+use std::{collections::BTreeMap, fs, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{filter::Field, utils};
+
+const CONFIG_FILE: &str = ".config.toml";
+
+/// User settings, kept next to the `.token` file.
+///
+/// ```toml
+/// default_table = "work"
+///
+/// [aliases]
+/// w = "work"
+///
+/// [display]
+/// style = "psql"
+/// columns = ["due", "description"]
+/// dues = "both"
+/// overdue_color = "bold red"
+/// ```
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Table of the commands given none
+    pub default_table: Option<String>,
+    /// Other names for tables, resolved before calling the api
+    pub aliases: BTreeMap<String, String>,
+    /// Kept as written, so a bad value only falls back to the defaults, see `display_config`
+    pub display: toml::Table,
+}
+
+/// How the tasks are rendered.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplayConfig {
+    pub style: TableStyle,
+    /// Colour names and 'bold', e.g. "bold green", or "none"
+    pub header_color: String,
+    pub id_color: String,
+    /// Columns of the task tables in order, those a table doesn't have are skipped
+    pub columns: Vec<Field>,
+    /// Format of the dates, as in `chrono::format::strftime`
+    pub date_format: String,
+    pub dues: DueStyle,
+    /// Colours the overdue, due today and due soon rows
+    pub due_colors: bool,
+    pub overdue_color: String,
+    pub today_color: String,
+    pub soon_color: String,
+    /// How far ahead a due is soon
+    pub soon_hours: u32,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        DisplayConfig {
+            style: TableStyle::Rounded,
+            header_color: "bold green".to_string(),
+            id_color: "bold".to_string(),
+            columns: vec![Field::Id, Field::Description, Field::Due, Field::Group],
+            date_format: "%Y-%m-%d %H:%M".to_string(),
+            dues: DueStyle::Absolute,
+            due_colors: true,
+            overdue_color: "red".to_string(),
+            today_color: "yellow".to_string(),
+            soon_color: "cyan".to_string(),
+            soon_hours: 48,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TableStyle {
+    Rounded,
+    Ascii,
+    Markdown,
+    Psql,
+    Blank,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DueStyle {
+    /// `2026-10-20 10:00`
+    Absolute,
+    /// `in 3h`, `overdue 2d`
+    Relative,
+    /// `2026-10-20 10:00 (in 3h)`
+    Both,
+}
+
+impl Config {
+    /// Loads the config, a missing file is an empty config.
+    pub fn load() -> Result<Config, String> {
+        let path = utils::data_path(CONFIG_FILE);
+
+        match fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content)
+                .map_err(|e| format!("Invalid config file '{}': {e}", path.display())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(format!(
+                "Internal error: couldn't read the config file: {e}"
+            )),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let content = toml::to_string(self).map_err(|e| format!("Internal error: {e}"))?;
+
+        fs::write(utils::data_path(CONFIG_FILE), content)
+            .map_err(|e| format!("Internal error: couldn't write the config file: {e}"))
+    }
+
+    /// The '[display]' section, with the defaults of the keys left out.
+    pub fn display_config(&self) -> Result<DisplayConfig, String> {
+        toml::Value::Table(self.display.clone())
+            .try_into()
+            .map_err(|e: toml::de::Error| {
+                // the message spans lines, e.g. the variants then the key
+                let message = e.to_string();
+                let message: Vec<&str> = message.lines().map(str::trim).collect();
+                format!("Invalid config file: '[display]' {}", message.join(" "))
+            })
+    }
+
+    /// The table an alias stands for, or `tablename` itself when it's not an alias.
+    pub fn resolve(&self, tablename: &str) -> String {
+        self.aliases
+            .get(tablename)
+            .cloned()
+            .unwrap_or_else(|| tablename.to_string())
+    }
+}
@@ -0,0 +1,524 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+    process::Command,
+};
+
+use serde_json::Value;
+
+use crate::utils::{self, parse_due, Due};
+
+// marks the error added at the end of a document that didn't parse
+const ERROR_COMMENT: &str = "# Error: ";
+
+/// Opens `content` in the user's editor (`$VISUAL`, then `$EDITOR`, falling back to `vi`)
+/// and parses the saved text with `parse`.
+///
+/// A text that doesn't parse is reopened with the error as a comment at its end, so no
+/// edit is lost. When the user doesn't reopen it, the file is left in place and its path
+/// is in the error.
+pub fn edit_text<T>(
+    content: &str,
+    mut parse: impl FnMut(&str) -> Result<T, String>,
+) -> Result<T, String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|e| !e.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+
+    // the editor can come with arguments, e.g. 'code --wait'
+    let args = shlex::split(&editor)
+        .filter(|args| !args.is_empty())
+        .ok_or_else(|| format!("Invalid editor command '{editor}'"))?;
+
+    let (path, mut file) = create_temp_file().map_err(|e| format!("Internal error: {e}"))?;
+    let written = file.write_all(content.as_bytes());
+    drop(file);
+    if let Err(e) = written {
+        let _ = fs::remove_file(&path);
+        return Err(format!("Internal error: {e}"));
+    }
+
+    let kept = |e: String| format!("{e}, the edits are kept in '{}'", path.display());
+    let mut edited = false;
+    loop {
+        let status = Command::new(&args[0]).args(&args[1..]).arg(&path).status();
+        let error = match status {
+            Ok(status) if status.success() => None,
+            Ok(_) => Some(format!("The editor '{editor}' exited with an error")),
+            Err(e) => Some(format!("Couldn't launch the editor '{editor}': {e}")),
+        };
+        if let Some(e) = error {
+            // the first time there's nothing of the user's to keep
+            if !edited {
+                let _ = fs::remove_file(&path);
+                return Err(e);
+            }
+            return Err(kept(e));
+        }
+        edited = true;
+
+        let doc = fs::read_to_string(&path).map_err(|e| kept(format!("Internal error: {e}")))?;
+        let e = match parse(&doc) {
+            Ok(parsed) => {
+                let _ = fs::remove_file(&path);
+                return Ok(parsed);
+            }
+            Err(e) => e,
+        };
+
+        println!("Error: {e}");
+        let again = io::stdin().is_terminal()
+            && utils::prompt_confirm("Edit the file again?")
+                .map_err(|e| kept(format!("Internal error: {e}")))?;
+        if !again {
+            return Err(format!("The edits are kept in '{}'", path.display()));
+        }
+
+        // at the end, so the line numbers of the errors stay right
+        let mut lines: Vec<&str> = doc.lines().collect();
+        while lines
+            .last()
+            .is_some_and(|line| line.starts_with(ERROR_COMMENT))
+        {
+            lines.pop();
+        }
+        let doc = format!("{}\n{ERROR_COMMENT}{e}\n", lines.join("\n"));
+        fs::write(&path, doc).map_err(|e| kept(format!("Internal error: {e}")))?;
+    }
+}
+
+/// Creates a new file only the user can read in the temp dir.
+///
+/// The file must not exist yet, so a file or a symlink planted there by someone else
+/// is never written through, another name is tried instead.
+fn create_temp_file() -> io::Result<(PathBuf, File)> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let mut attempts = 0;
+    loop {
+        let nanos = chrono::Local::now().timestamp_subsec_nanos();
+        let path =
+            std::env::temp_dir().join(format!("rsm-edit-{}-{nanos:09}.txt", std::process::id()));
+
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists && attempts < 10 => attempts += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// The editable fields of a task, as strings the way they are shown to the user.
+#[derive(Debug, Default, PartialEq)]
+pub struct TaskFields {
+    pub description: String,
+    // None when the table has no due (or group) support
+    pub due: Option<String>,
+    pub group: Option<String>,
+}
+
+impl TaskFields {
+    pub fn from_json(task: &Value) -> Self {
+        TaskFields {
+            description: task
+                .get("description")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            due: task.get("due").map(|due| {
+                Due::from_api(due)
+                    .map(|d| d.0.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_default()
+            }),
+            group: task
+                .get("group")
+                .map(|group| group.as_str().unwrap_or_default().to_string()),
+        }
+    }
+}
+
+/// Renders a task as an editable document, one `field: value` per line.
+pub fn task_document(tablename: &str, id: usize, fields: &TaskFields) -> String {
+    let mut doc = format!(
+        "# Editing task {id} of table '{tablename}'\n\
+         # Lines starting with '#' are ignored, saving an empty file cancels the edit.\n"
+    );
+    if fields.due.is_some() {
+        doc.push_str("# The due is in the format of 'hh:mm' or 'YYYY-MM-dd hh:mm'.\n");
+    }
+
+    doc.push_str(&format!("description: {}\n", fields.description));
+    if let Some(due) = &fields.due {
+        doc.push_str(&format!("due: {due}\n"));
+    }
+    if let Some(group) = &fields.group {
+        doc.push_str(&format!("group: {group}\n"));
+    }
+
+    doc
+}
+
+/// Parses a document written by `task_document` back, `None` means it was emptied.
+///
+/// Only the fields present in `original` can be set, so a table without due
+/// support can't get one through the editor.
+pub fn parse_task_document(doc: &str, original: &TaskFields) -> Result<Option<TaskFields>, String> {
+    let mut fields = TaskFields::default();
+    let mut empty = true;
+
+    for (n, line) in doc.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        empty = false;
+
+        let (key, value) = line
+            .split_once(':')
+            .ok_or_else(|| format!("Line {}: expected 'field: value', found '{line}'", n + 1))?;
+        let value = value.trim().to_string();
+
+        match key.trim() {
+            "description" => fields.description = value,
+            "due" if original.due.is_some() => {
+                if !value.is_empty() {
+                    parse_due(&value).map_err(|e| format!("Line {}: {e}", n + 1))?;
+                }
+                fields.due = Some(value);
+            }
+            "group" if original.group.is_some() => fields.group = Some(value),
+            key @ ("due" | "group") => {
+                return Err(format!("Line {}: the table doesn't support a {key}", n + 1))
+            }
+            key => return Err(format!("Line {}: unknown field '{key}'", n + 1)),
+        }
+    }
+
+    if empty {
+        return Ok(None);
+    }
+
+    if fields.description.is_empty() {
+        return Err("The description can't be empty".to_string());
+    }
+
+    // a field removed from the document is left as it was
+    if fields.due.is_none() {
+        fields.due = original.due.clone();
+    }
+    if fields.group.is_none() {
+        fields.group = original.group.clone();
+    }
+
+    Ok(Some(fields))
+}
+
+/// What changed between two versions of a task, ready to be passed to `Api::update_task`.
+#[derive(Debug, Default)]
+pub struct TaskChanges {
+    pub description: Option<String>,
+    pub due: Option<Due>,
+    pub group: Option<String>,
+}
+
+impl TaskChanges {
+    pub fn is_empty(&self) -> bool {
+        self.description.is_none() && self.due.is_none() && self.group.is_none()
+    }
+}
+
+/// Computes the changes from `original` to `edited`.
+///
+/// The api has no way to unset a due or a group, so clearing one is an error.
+pub fn diff_task(original: &TaskFields, edited: &TaskFields) -> Result<TaskChanges, String> {
+    let mut changes = TaskChanges::default();
+
+    // the document is trimmed when parsed, so the original is compared the same way
+    if edited.description != original.description.trim() {
+        changes.description = Some(edited.description.clone());
+    }
+
+    if edited.due != original.due {
+        match edited.due.as_deref() {
+            Some("") | None => return Err("A due can't be removed from a task".to_string()),
+            Some(due) => changes.due = Some(parse_due(due)?),
+        }
+    }
+
+    if edited.group != original.group {
+        match edited.group.as_deref() {
+            Some("") | None => return Err("A group can't be removed from a task".to_string()),
+            Some(group) => changes.group = Some(group.to_string()),
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Renders a whole table as an editable document, one `id | due | group | description` per line.
+///
+/// The due and group columns are only there when the table supports them.
+pub fn table_document(tablename: &str, has_due: bool, has_group: bool, tasks: &[Value]) -> String {
+    let mut columns = vec!["id"];
+    if has_due {
+        columns.push("due");
+    }
+    if has_group {
+        columns.push("group");
+    }
+    columns.push("description");
+
+    let mut doc = format!(
+        "# Editing table '{tablename}', one task per line: {}\n\
+         # Delete a line to remove its task, use '-' as id to add a new one.\n\
+         # Lines starting with '#' are ignored, saving an empty file cancels the edit.\n",
+        columns.join(" | ")
+    );
+    if has_due {
+        doc.push_str("# The due is in the format of 'hh:mm' or 'YYYY-MM-dd hh:mm'.\n");
+    }
+
+    for task in tasks {
+        let id = task.get("id").and_then(|v| v.as_u64()).unwrap_or_default();
+        let fields = TaskFields::from_json(task);
+
+        let mut line = vec![id.to_string()];
+        if has_due {
+            line.push(fields.due.unwrap_or_default());
+        }
+        if has_group {
+            line.push(fields.group.unwrap_or_default());
+        }
+        line.push(fields.description);
+
+        doc.push_str(&line.join(" | "));
+        doc.push('\n');
+    }
+
+    doc
+}
+
+/// The calls needed to turn a table into its edited document.
+#[derive(Debug, Default)]
+pub struct TablePlan {
+    pub adds: Vec<TaskFields>,
+    pub updates: Vec<(usize, TaskChanges)>,
+    pub removes: Vec<usize>,
+}
+
+impl TablePlan {
+    pub fn is_empty(&self) -> bool {
+        self.adds.is_empty() && self.updates.is_empty() && self.removes.is_empty()
+    }
+}
+
+/// Parses a document written by `table_document` back and diffs it against `tasks`,
+/// `None` means it was emptied.
+pub fn parse_table_document(
+    doc: &str,
+    has_due: bool,
+    has_group: bool,
+    tasks: &[Value],
+) -> Result<Option<TablePlan>, String> {
+    let originals: Vec<(usize, TaskFields)> = tasks
+        .iter()
+        .map(|task| {
+            let id = task.get("id").and_then(|v| v.as_u64()).unwrap_or_default() as usize;
+            (id, TaskFields::from_json(task))
+        })
+        .collect();
+
+    // description is last so it can contain the separator
+    let n_columns = 2 + has_due as usize + has_group as usize;
+
+    let mut plan = TablePlan::default();
+    let mut kept = vec![];
+    let mut empty = true;
+
+    for (n, line) in doc.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        empty = false;
+
+        let mut parts = line.splitn(n_columns, '|').map(str::trim);
+        let id = parts.next().unwrap_or_default();
+        let due = has_due.then(|| parts.next().unwrap_or_default().to_string());
+        let group = has_group.then(|| parts.next().unwrap_or_default().to_string());
+        let description = parts
+            .next()
+            .ok_or_else(|| {
+                format!(
+                    "Line {}: expected {n_columns} columns separated by '|'",
+                    n + 1
+                )
+            })?
+            .to_string();
+
+        if description.is_empty() {
+            return Err(format!("Line {}: the description can't be empty", n + 1));
+        }
+        if let Some(due) = due.as_deref().filter(|due| !due.is_empty()) {
+            parse_due(due).map_err(|e| format!("Line {}: {e}", n + 1))?;
+        }
+
+        let fields = TaskFields {
+            description,
+            due,
+            group,
+        };
+
+        if id == "-" || id.is_empty() {
+            plan.adds.push(fields);
+            continue;
+        }
+
+        let id: usize = id
+            .parse()
+            .map_err(|_| format!("Line {}: invalid id '{id}'", n + 1))?;
+        let original = originals
+            .iter()
+            .find(|(original_id, _)| *original_id == id)
+            .map(|(_, original)| original)
+            .ok_or_else(|| format!("Line {}: there is no task with id {id}", n + 1))?;
+
+        if kept.contains(&id) {
+            return Err(format!("Line {}: task {id} appears more than once", n + 1));
+        }
+        kept.push(id);
+
+        let changes = diff_task(original, &fields).map_err(|e| format!("Line {}: {e}", n + 1))?;
+        if !changes.is_empty() {
+            plan.updates.push((id, changes));
+        }
+    }
+
+    if empty {
+        return Ok(None);
+    }
+
+    plan.removes = originals
+        .iter()
+        .map(|(id, _)| *id)
+        .filter(|id| !kept.contains(id))
+        .collect();
+
+    Ok(Some(plan))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn tasks() -> Vec<Value> {
+        vec![
+            json!({"id": 1, "description": "call bob", "group": "work"}),
+            json!({"id": 2, "description": "a | b", "group": "home"}),
+            json!({"id": 3, "description": "pay rent", "group": "home"}),
+        ]
+    }
+
+    fn plan(doc: &str) -> Result<Option<TablePlan>, String> {
+        parse_table_document(doc, false, true, &tasks())
+    }
+
+    #[test]
+    fn lines_are_kept_removed_and_added() {
+        let plan = plan(
+            "# a comment\n1 | work | call bob\n3 | home | pay rent twice\n- | work | new one\n",
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(plan.removes, [2]);
+        assert_eq!(plan.updates.len(), 1);
+        assert_eq!(plan.updates[0].0, 3);
+        assert_eq!(
+            plan.updates[0].1.description.as_deref(),
+            Some("pay rent twice")
+        );
+        assert_eq!(plan.adds.len(), 1);
+        assert_eq!(plan.adds[0].description, "new one");
+        assert_eq!(plan.adds[0].group.as_deref(), Some("work"));
+    }
+
+    #[test]
+    fn the_description_can_hold_the_separator() {
+        let unchanged = plan("1 | work | call bob\n2 | home | a | b\n3 | home | pay rent\n")
+            .unwrap()
+            .unwrap();
+        assert!(unchanged.is_empty());
+
+        let changed = plan("1 | work | call bob\n2 | work | a | c\n3 | home | pay rent\n")
+            .unwrap()
+            .unwrap();
+        let (id, changes) = &changed.updates[0];
+        assert_eq!(*id, 2);
+        assert_eq!(changes.description.as_deref(), Some("a | c"));
+        assert_eq!(changes.group.as_deref(), Some("work"));
+    }
+
+    #[test]
+    fn bad_lines_are_errors() {
+        assert!(plan("1 | work | call bob\n1 | work | call bob\n")
+            .unwrap_err()
+            .contains("more than once"));
+        assert!(plan("7 | work | who\n")
+            .unwrap_err()
+            .contains("no task with id 7"));
+        assert!(plan("x | work | call bob\n")
+            .unwrap_err()
+            .contains("invalid id"));
+        assert!(plan("1 | call bob\n").unwrap_err().contains("3 columns"));
+        assert!(plan("1 | | call bob\n")
+            .unwrap_err()
+            .contains("A group can't be removed"));
+    }
+
+    #[test]
+    fn an_emptied_document_cancels() {
+        assert!(plan("# only comments\n\n").unwrap().is_none());
+        // every line deleted but one is a removal of the others
+        assert_eq!(
+            plan("1 | work | call bob\n").unwrap().unwrap().removes,
+            [2, 3]
+        );
+    }
+
+    #[test]
+    fn only_changed_fields_are_sent() {
+        let original = TaskFields {
+            description: "call bob ".to_string(),
+            due: Some("2026-10-20 10:00".to_string()),
+            group: Some("work".to_string()),
+        };
+        let edited = |description: &str, due: &str, group: &str| TaskFields {
+            description: description.to_string(),
+            due: Some(due.to_string()),
+            group: Some(group.to_string()),
+        };
+
+        // the original is compared trimmed, like the document is parsed
+        let changes =
+            diff_task(&original, &edited("call bob", "2026-10-20 10:00", "work")).unwrap();
+        assert!(changes.is_empty());
+
+        let changes =
+            diff_task(&original, &edited("call bob", "2026-10-21 10:00", "home")).unwrap();
+        assert!(changes.description.is_none());
+        assert!(changes.due.is_some());
+        assert_eq!(changes.group.as_deref(), Some("home"));
+
+        assert!(diff_task(&original, &edited("call bob", "", "work")).is_err());
+        assert!(diff_task(&original, &edited("call bob", "2026-10-20 10:00", "")).is_err());
+    }
+}
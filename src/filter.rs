@@ -0,0 +1,678 @@
+use std::cmp::Ordering;
+
+use chrono::{Duration, Local, NaiveDate, NaiveDateTime, NaiveTime};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Client-side filter for the tasks of a table, parsed from an expression like
+/// `due < tomorrow and group != home and description ~ "deploy"`.
+///
+/// Comparisons are made of a field (`id`, `description`, `due`, `group`), an operator
+/// (`=`, `!=`, `<`, `<=`, `>`, `>=`, `~` for a case insensitive regex match, `!~`) and a value,
+/// they can be combined with `and`, `or`, `not` and parentheses.
+/// Dues accept `now`, `today`, `tomorrow`, `yesterday`, 'YYYY-MM-dd' or 'YYYY-MM-dd hh:mm',
+/// `none` matches a missing due or group.
+#[derive(Debug, Clone)]
+pub enum Filter {
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    Compare(Field, Op, Operand),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Field {
+    Id,
+    Description,
+    Due,
+    Group,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Match,
+    NotMatch,
+}
+
+#[derive(Debug, Clone)]
+pub enum Operand {
+    None,
+    Number(i64),
+    Text(String),
+    // a date without a time covers the whole day
+    Date {
+        start: NaiveDateTime,
+        whole_day: bool,
+    },
+    Regex(Regex),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(Op),
+    LParen,
+    RParen,
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(w) => write!(f, "'{w}'"),
+            Token::Quoted(q) => write!(f, "\"{q}\""),
+            Token::Op(_) => write!(f, "an operator"),
+            Token::LParen => write!(f, "'('"),
+            Token::RParen => write!(f, "')'"),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Token)>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        // columns are 1-based in error messages
+        let col = i + 1;
+
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push((col, Token::LParen));
+                i += 1;
+            }
+            ')' => {
+                tokens.push((col, Token::RParen));
+                i += 1;
+            }
+            '"' | '\'' => {
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => {
+                            return Err(format!("unterminated string starting at column {col}"))
+                        }
+                        Some('\\') if chars.get(i + 1).is_some() => {
+                            value.push(chars[i + 1]);
+                            i += 2;
+                        }
+                        Some(&q) if q == c => {
+                            i += 1;
+                            break;
+                        }
+                        Some(&other) => {
+                            value.push(other);
+                            i += 1;
+                        }
+                    }
+                }
+                tokens.push((col, Token::Quoted(value)));
+            }
+            '=' | '!' | '<' | '>' | '~' => {
+                let next = chars.get(i + 1).copied();
+                let (op, len) = match (c, next) {
+                    ('=', Some('=')) => (Op::Eq, 2),
+                    ('=', _) => (Op::Eq, 1),
+                    ('!', Some('=')) => (Op::Ne, 2),
+                    ('!', Some('~')) => (Op::NotMatch, 2),
+                    ('<', Some('=')) => (Op::Le, 2),
+                    ('<', _) => (Op::Lt, 1),
+                    ('>', Some('=')) => (Op::Ge, 2),
+                    ('>', _) => (Op::Gt, 1),
+                    ('~', _) => (Op::Match, 1),
+                    _ => return Err(format!("unexpected '{c}' at column {col}")),
+                };
+                tokens.push((col, Token::Op(op)));
+                i += len;
+            }
+            _ => {
+                let start = i;
+                while i < chars.len()
+                    && !chars[i].is_whitespace()
+                    && !"()=!<>~\"'".contains(chars[i])
+                {
+                    i += 1;
+                }
+                tokens.push((col, Token::Word(chars[start..i].iter().collect())));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    // column right after the input, used when it ends too early
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, t)| t)
+    }
+
+    fn column(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .map(|(col, _)| *col)
+            .unwrap_or(self.end)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).map(|(_, t)| t.clone());
+        self.pos += 1;
+        token
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword))
+    }
+
+    fn error(&self, expected: &str) -> String {
+        match self.peek() {
+            Some(token) => format!(
+                "expected {expected} at column {}, found {token}",
+                self.column()
+            ),
+            None => format!("expected {expected} at column {}", self.column()),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Filter, String> {
+        let mut lhs = self.parse_and()?;
+        while self.is_keyword("or") || self.is_keyword("||") {
+            self.pos += 1;
+            lhs = Filter::Or(Box::new(lhs), Box::new(self.parse_and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Filter, String> {
+        let mut lhs = self.parse_unary()?;
+        while self.is_keyword("and") || self.is_keyword("&&") {
+            self.pos += 1;
+            lhs = Filter::And(Box::new(lhs), Box::new(self.parse_unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Filter, String> {
+        if self.is_keyword("not") {
+            self.pos += 1;
+            return Ok(Filter::Not(Box::new(self.parse_unary()?)));
+        }
+
+        if self.peek() == Some(&Token::LParen) {
+            self.pos += 1;
+            let inner = self.parse_or()?;
+            if self.peek() != Some(&Token::RParen) {
+                return Err(self.error("')'"));
+            }
+            self.pos += 1;
+            return Ok(inner);
+        }
+
+        self.parse_comparison()
+    }
+
+    fn parse_comparison(&mut self) -> Result<Filter, String> {
+        let field = match self.peek() {
+            Some(Token::Word(w)) => match w.to_lowercase().as_str() {
+                "id" => Field::Id,
+                "description" | "desc" | "task" => Field::Description,
+                "due" => Field::Due,
+                "group" => Field::Group,
+                _ => {
+                    return Err(format!(
+                    "unknown field '{w}' at column {}, expected one of id, description, due, group",
+                    self.column()
+                ))
+                }
+            },
+            _ => return Err(self.error("a field")),
+        };
+        self.pos += 1;
+
+        let op = match self.peek() {
+            Some(Token::Op(op)) => *op,
+            _ => return Err(self.error("an operator")),
+        };
+        if matches!(op, Op::Match | Op::NotMatch) && matches!(field, Field::Id | Field::Due) {
+            return Err(format!(
+                "'~' can only be used on the description or the group, at column {}",
+                self.column()
+            ));
+        }
+        self.pos += 1;
+
+        let column = self.column();
+        let value = match self.next() {
+            Some(Token::Word(w)) => w,
+            Some(Token::Quoted(q)) => q,
+            _ => {
+                self.pos -= 1;
+                return Err(self.error("a value"));
+            }
+        };
+
+        let operand =
+            parse_operand(field, op, &value).map_err(|e| format!("{e} at column {column}"))?;
+
+        Ok(Filter::Compare(field, op, operand))
+    }
+}
+
+fn parse_operand(field: Field, op: Op, value: &str) -> Result<Operand, String> {
+    if matches!(op, Op::Match | Op::NotMatch) {
+        return regex::RegexBuilder::new(value)
+            .case_insensitive(true)
+            .build()
+            .map(Operand::Regex)
+            .map_err(|_| format!("invalid regex '{value}'"));
+    }
+
+    if value.eq_ignore_ascii_case("none") {
+        return match op {
+            Op::Eq | Op::Ne => Ok(Operand::None),
+            _ => Err("'none' can only be compared with '=' or '!='".to_string()),
+        };
+    }
+
+    match field {
+        Field::Id => value
+            .parse()
+            .map(Operand::Number)
+            .map_err(|_| format!("invalid id '{value}'")),
+        Field::Due => parse_date(value).ok_or_else(|| {
+            format!(
+                "invalid date '{value}', expected now, today, tomorrow, yesterday, 'YYYY-MM-dd' or 'YYYY-MM-dd hh:mm'"
+            )
+        }),
+        Field::Description | Field::Group => Ok(Operand::Text(value.to_string())),
+    }
+}
+
+fn parse_date(value: &str) -> Option<Operand> {
+    let now = Local::now().naive_local();
+    let today = now.date().and_time(NaiveTime::MIN);
+
+    let day = |start| {
+        Some(Operand::Date {
+            start,
+            whole_day: true,
+        })
+    };
+
+    match value.to_lowercase().as_str() {
+        "now" => Some(Operand::Date {
+            start: now,
+            whole_day: false,
+        }),
+        "today" => day(today),
+        "tomorrow" => day(today + Duration::days(1)),
+        "yesterday" => day(today - Duration::days(1)),
+        _ => {
+            if let Ok(dt) = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M") {
+                return Some(Operand::Date {
+                    start: dt,
+                    whole_day: false,
+                });
+            }
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| day(d.and_time(NaiveTime::MIN)))
+        }
+    }
+}
+
+/// Parser for `--due-before`, a date as accepted by the expressions.
+pub fn parse_due_before(value: &str) -> Result<Filter, String> {
+    parse_operand(Field::Due, Op::Lt, value)
+        .map(|operand| Filter::Compare(Field::Due, Op::Lt, operand))
+}
+
+/// Parser for `--match`, a case insensitive regex on the description.
+pub fn parse_match(value: &str) -> Result<Filter, String> {
+    parse_operand(Field::Description, Op::Match, value)
+        .map(|operand| Filter::Compare(Field::Description, Op::Match, operand))
+}
+
+/// Custom parser for the `--where` expression.
+pub fn parse_filter(input: &str) -> Result<Filter, String> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Err("empty filter".to_string());
+    }
+
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        end: input.chars().count() + 1,
+    };
+    let filter = parser.parse_or()?;
+
+    if parser.peek().is_some() {
+        return Err(parser.error("'and', 'or' or the end of the filter"));
+    }
+
+    Ok(filter)
+}
+
+fn task_str<'a>(task: &'a Value, key: &str) -> Option<&'a str> {
+    task.get(key).and_then(|v| v.as_str())
+}
+
+fn task_due(task: &Value) -> Option<NaiveDateTime> {
+    task_str(task, "due")
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+        .map(|dt| dt.naive_local())
+}
+
+fn compare<T: PartialOrd>(lhs: T, op: Op, rhs: T) -> bool {
+    match op {
+        Op::Eq => lhs == rhs,
+        Op::Ne => lhs != rhs,
+        Op::Lt => lhs < rhs,
+        Op::Le => lhs <= rhs,
+        Op::Gt => lhs > rhs,
+        Op::Ge => lhs >= rhs,
+        Op::Match | Op::NotMatch => false,
+    }
+}
+
+impl Filter {
+    /// Checks a task, as returned by the api, against the filter.
+    pub fn matches(&self, task: &Value) -> bool {
+        match self {
+            Filter::And(lhs, rhs) => lhs.matches(task) && rhs.matches(task),
+            Filter::Or(lhs, rhs) => lhs.matches(task) || rhs.matches(task),
+            Filter::Not(inner) => !inner.matches(task),
+            Filter::Compare(field, op, operand) => compare_field(task, *field, *op, operand),
+        }
+    }
+}
+
+fn compare_field(task: &Value, field: Field, op: Op, operand: &Operand) -> bool {
+    let text = match field {
+        Field::Description => task_str(task, "description"),
+        Field::Group => task_str(task, "group"),
+        Field::Id | Field::Due => None,
+    };
+
+    match operand {
+        Operand::None => {
+            let missing = match field {
+                Field::Due => task_due(task).is_none(),
+                Field::Id => false,
+                _ => text.is_none_or(str::is_empty),
+            };
+            missing == (op == Op::Eq)
+        }
+        Operand::Regex(re) => {
+            let found = text.is_some_and(|t| re.is_match(t));
+            found == (op == Op::Match)
+        }
+        Operand::Number(n) => task
+            .get("id")
+            .and_then(|v| v.as_i64())
+            .is_some_and(|id| compare(id, op, *n)),
+        Operand::Text(value) => match text {
+            Some(t) => compare(t, op, value.as_str()),
+            // a missing value is only different from anything
+            None => op == Op::Ne,
+        },
+        Operand::Date { start, whole_day } => match task_due(task) {
+            Some(due) if *whole_day => {
+                let end = *start + Duration::days(1);
+                match op {
+                    Op::Eq => due >= *start && due < end,
+                    Op::Ne => due < *start || due >= end,
+                    Op::Lt => due < *start,
+                    Op::Le => due < end,
+                    Op::Gt => due >= end,
+                    Op::Ge => due >= *start,
+                    Op::Match | Op::NotMatch => false,
+                }
+            }
+            Some(due) => compare(due, op, *start),
+            None => op == Op::Ne,
+        },
+    }
+}
+
+/// Sort order for the tasks of a table, parsed from a list like `due:desc,id`.
+#[derive(Debug, Clone)]
+pub struct SortSpec(Vec<(Field, bool)>);
+
+/// Custom parser for the `--sort-by` keys, each key can be followed by `:asc` or `:desc`.
+pub fn parse_sort(input: &str) -> Result<SortSpec, String> {
+    let mut keys = vec![];
+
+    for key in input.split(',').map(str::trim) {
+        let (name, direction) = key.split_once(':').unwrap_or((key, "asc"));
+
+        let field = match name.to_lowercase().as_str() {
+            "id" => Field::Id,
+            "description" | "desc" | "task" => Field::Description,
+            "due" => Field::Due,
+            "group" => Field::Group,
+            "" => return Err(format!("Empty sort key in '{input}'")),
+            _ => {
+                return Err(format!(
+                    "Invalid sort key '{name}', expected one of id, description, due, group"
+                ))
+            }
+        };
+
+        let descending = match direction.to_lowercase().as_str() {
+            "asc" => false,
+            "desc" => true,
+            _ => {
+                return Err(format!(
+                    "Invalid sort direction '{direction}', expected 'asc' or 'desc'"
+                ))
+            }
+        };
+
+        keys.push((field, descending));
+    }
+
+    Ok(SortSpec(keys))
+}
+
+/// A string ordered case insensitively, without allocating a lowercase copy.
+#[derive(PartialEq, Eq)]
+struct Caseless<'a>(&'a str);
+
+impl Ord for Caseless<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0
+            .chars()
+            .flat_map(char::to_lowercase)
+            .cmp(other.0.chars().flat_map(char::to_lowercase))
+    }
+}
+
+impl PartialOrd for Caseless<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Tasks without the value always go last, whatever the direction.
+fn missing_last<T: Ord>(a: Option<T>, b: Option<T>, descending: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if descending => b.cmp(&a),
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+fn compare_tasks(a: &Value, b: &Value, field: Field, descending: bool) -> Ordering {
+    match field {
+        Field::Id => missing_last(
+            a.get("id").and_then(|v| v.as_i64()),
+            b.get("id").and_then(|v| v.as_i64()),
+            descending,
+        ),
+        Field::Due => missing_last(task_due(a), task_due(b), descending),
+        Field::Description => missing_last(
+            task_str(a, "description").map(Caseless),
+            task_str(b, "description").map(Caseless),
+            descending,
+        ),
+        Field::Group => missing_last(
+            task_str(a, "group").map(Caseless),
+            task_str(b, "group").map(Caseless),
+            descending,
+        ),
+    }
+}
+
+impl SortSpec {
+    pub fn sort(&self, tasks: &mut [Value]) {
+        tasks.sort_by(|a, b| {
+            self.0
+                .iter()
+                .map(|(field, descending)| compare_tasks(a, b, *field, *descending))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn tasks() -> Vec<Value> {
+        vec![
+            json!({"id": 1, "description": "Deploy the api", "due": "2026-10-19T10:00:00+02:00", "group": "work"}),
+            json!({"id": 2, "description": "buy milk", "due": null, "group": "home"}),
+            json!({"id": 3, "description": "call \"bob\"", "due": "2026-10-21T18:00:00+02:00", "group": null}),
+        ]
+    }
+
+    /// The ids of the tasks matching `input`.
+    fn matching(input: &str) -> Vec<i64> {
+        let filter = parse_filter(input).unwrap();
+        tasks()
+            .iter()
+            .filter(|task| filter.matches(task))
+            .filter_map(|task| task.get("id").and_then(|v| v.as_i64()))
+            .collect()
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        assert_eq!(matching("id = 1 or id = 2 and group = work"), [1]);
+        assert_eq!(matching("(id = 1 or id = 2) and group = home"), [2]);
+    }
+
+    #[test]
+    fn not_applies_to_the_next_comparison_only() {
+        assert_eq!(matching("not id = 1 and group != none"), [2]);
+        assert_eq!(matching("not (id = 1 or id = 2)"), [3]);
+        assert_eq!(matching("NOT id > 1 || id = 3"), [1, 3]);
+    }
+
+    #[test]
+    fn quoted_values_keep_spaces_and_escapes() {
+        assert_eq!(matching("description = 'buy milk'"), [2]);
+        assert_eq!(matching(r#"description ~ "call \"bob\"""#), [3]);
+        assert_eq!(matching(r#"desc ~ 'deploy (the|an)'"#), [1]);
+    }
+
+    #[test]
+    fn none_matches_missing_values() {
+        assert_eq!(matching("due = none"), [2]);
+        assert_eq!(matching("group = none"), [3]);
+        assert_eq!(matching("due != none and group != none"), [1]);
+    }
+
+    #[test]
+    fn dates_without_time_cover_the_whole_day() {
+        assert_eq!(matching("due = 2026-10-19"), [1]);
+        assert_eq!(matching("due < '2026-10-21 18:00'"), [1]);
+        assert_eq!(matching("due <= 2026-10-21"), [1, 3]);
+    }
+
+    #[test]
+    fn errors_point_at_the_column() {
+        let error = |input| parse_filter(input).unwrap_err();
+
+        assert_eq!(error(""), "empty filter");
+        assert_eq!(
+            error("description = 'x"),
+            "unterminated string starting at column 15"
+        );
+        assert_eq!(
+            error("colour = red"),
+            "unknown field 'colour' at column 1, expected one of id, description, due, group"
+        );
+        assert_eq!(error("id"), "expected an operator at column 3");
+        assert_eq!(error("(id = 1"), "expected ')' at column 8");
+        assert_eq!(
+            error("id = 1 group = x"),
+            "expected 'and', 'or' or the end of the filter at column 8, found 'group'"
+        );
+        assert_eq!(error("id = x"), "invalid id 'x' at column 6");
+        assert_eq!(error("due < 1"), "invalid date '1', expected now, today, tomorrow, yesterday, 'YYYY-MM-dd' or 'YYYY-MM-dd hh:mm' at column 7");
+        assert_eq!(
+            error("group > none"),
+            "'none' can only be compared with '=' or '!=' at column 9"
+        );
+    }
+
+    #[test]
+    fn regex_is_only_allowed_on_text_fields() {
+        assert_eq!(
+            parse_filter("due ~ x").unwrap_err(),
+            "'~' can only be used on the description or the group, at column 5"
+        );
+        assert_eq!(
+            parse_filter("id !~ 1").unwrap_err(),
+            "'~' can only be used on the description or the group, at column 4"
+        );
+        assert_eq!(
+            parse_filter("group ~ '('").unwrap_err(),
+            "invalid regex '(' at column 9"
+        );
+    }
+
+    #[test]
+    fn sort_puts_missing_values_last_in_both_directions() {
+        let ids = |spec: &str| {
+            let mut tasks = tasks();
+            parse_sort(spec).unwrap().sort(&mut tasks);
+            tasks
+                .iter()
+                .filter_map(|task| task.get("id").and_then(|v| v.as_i64()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids("due"), [1, 3, 2]);
+        assert_eq!(ids("due:desc"), [3, 1, 2]);
+        assert_eq!(ids("group:desc,id"), [1, 2, 3]);
+        assert_eq!(ids("description"), [2, 3, 1]);
+        assert_eq!(ids("id:desc"), [3, 2, 1]);
+    }
+}
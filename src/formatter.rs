@@ -1,80 +1,267 @@
+use std::{
+    io::{self, IsTerminal, Write},
+    process::{Command, Stdio},
+};
+
+use chrono::format::{Item, StrftimeItems};
+use ratatui::crossterm;
+use regex::Regex;
 use serde_json::Value;
 use tabled::{
+    builder::Builder,
     settings::{
-        object::{Columns, Object, Rows},
-        themes::Colorization,
+        object::{Columns, Object, Rows, Segment},
+        peaker::PriorityMax,
+        themes::{Colorization, Theme},
         Alignment, Color, Format, Style, Width,
     },
     Table, Tabled,
 };
 
-use crate::impl_table_parsing;
+use crate::{
+    capture::Capture,
+    config::{DisplayConfig, DueStyle, TableStyle},
+    filter::Field,
+    journal, utils,
+};
 
-#[derive(Tabled)]
-struct FullDataRow {
-    id: i32,
-    description: String,
-    due: String,
-    group: String,
-}
+// widest cell when the width of the terminal isn't known
+const FALLBACK_CELL_WIDTH: usize = 110;
 
-#[derive(Tabled)]
-struct NoDueDataRow {
-    id: i32,
-    description: String,
-    group: String,
+/// How the tables are rendered, from the config, the command line and the terminal.
+pub struct Display {
+    /// Off with '--no-color', the `NO_COLOR` environment variable or when stdout isn't a terminal
+    pub color: bool,
+    /// Borders of the tables, ASCII instead of rounded on terminals that can't draw them
+    pub style: TableStyle,
+    pub header_color: Option<Color>,
+    pub id_color: Option<Color>,
+    /// Columns of the task tables, in order
+    pub columns: Vec<Field>,
+    pub date_format: String,
+    /// Columns and lines of the terminal, when stdout is one
+    pub size: Option<(usize, usize)>,
+    /// Long tables go through `$PAGER`, unless '--no-pager'
+    pub pager: bool,
+    pub dues: DueStyle,
+    /// Colours of the overdue, due today and due soon rows, none when 'due_colors' is off
+    pub overdue_color: Option<Color>,
+    pub today_color: Option<Color>,
+    pub soon_color: Option<Color>,
+    pub soon: chrono::Duration,
 }
 
-#[derive(Tabled)]
-struct NoGroupDataRow {
-    id: i32,
-    description: String,
-    due: String,
+impl Display {
+    pub fn new(config: &DisplayConfig, no_color: bool, no_pager: bool) -> Result<Self, String> {
+        let tty = io::stdout().is_terminal();
+        // https://no-color.org, set and not empty
+        let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        let dumb = std::env::var("TERM").is_ok_and(|term| term == "dumb");
+
+        let size = tty
+            .then(crossterm::terminal::size)
+            .and_then(Result::ok)
+            .map(|(columns, lines)| (columns as usize, lines as usize));
+
+        if config.columns.is_empty() {
+            return Err("Invalid config file: 'display.columns' can't be empty".to_string());
+        }
+        if StrftimeItems::new(&config.date_format).any(|item| item == Item::Error) {
+            return Err(format!(
+                "Invalid config file: 'display.date_format' '{}' isn't a valid date format",
+                config.date_format
+            ));
+        }
+
+        Ok(Display {
+            color: tty && !dumb && !no_color && !no_color_env,
+            style: match config.style {
+                TableStyle::Rounded if dumb => TableStyle::Ascii,
+                style => style,
+            },
+            header_color: parse_color(&config.header_color)?,
+            id_color: parse_color(&config.id_color)?,
+            columns: config.columns.clone(),
+            date_format: config.date_format.clone(),
+            size,
+            pager: tty && !no_pager,
+            dues: config.dues,
+            overdue_color: parse_color(&config.overdue_color)?.filter(|_| config.due_colors),
+            today_color: parse_color(&config.today_color)?.filter(|_| config.due_colors),
+            soon_color: parse_color(&config.soon_color)?.filter(|_| config.due_colors),
+            soon: chrono::Duration::hours(config.soon_hours.into()),
+        })
+    }
+
+    /// Prints a table, through `$PAGER` when it's taller than the terminal.
+    pub fn print(&self, table: &Table) {
+        let output = table.to_string();
+
+        let too_tall = self
+            .size
+            .is_some_and(|(_, lines)| output.lines().count() >= lines);
+        if !self.pager || !too_tall || page(&output).is_err() {
+            println!("{output}");
+        }
+    }
+
+    fn theme(&self) -> Theme {
+        match self.style {
+            TableStyle::Rounded => Style::modern_rounded().into(),
+            TableStyle::Ascii => Style::ascii().into(),
+            TableStyle::Markdown => Style::markdown().into(),
+            TableStyle::Psql => Style::psql().into(),
+            TableStyle::Blank => Style::blank().into(),
+        }
+    }
+
+    /// Shrinks the widest columns, the descriptions, so the table fits in the terminal.
+    ///
+    /// Without a terminal, e.g. in a pipe, only the cells wider than `FALLBACK_CELL_WIDTH` are wrapped.
+    fn fit(&self, table: &mut Table) {
+        match self.size {
+            Some((columns, _)) => table.with(
+                Width::wrap(columns)
+                    .keep_words(true)
+                    .priority(PriorityMax::new(false)),
+            ),
+            None => table.modify(
+                Segment::all(),
+                Width::wrap(FALLBACK_CELL_WIDTH).keep_words(true),
+            ),
+        };
+    }
+
+    /// The due of a task as configured, "N/A" when it has none.
+    fn due(&self, task: &Value) -> String {
+        let Some(due) = task.get("due").and_then(utils::Due::from_api) else {
+            return "N/A".to_string();
+        };
+        let now = chrono::Local::now().naive_local();
+
+        let absolute = due.0.format(&self.date_format);
+        match self.dues {
+            DueStyle::Absolute => absolute.to_string(),
+            DueStyle::Relative => utils::relative_due(due.0, now),
+            DueStyle::Both => format!("{absolute} ({})", utils::relative_due(due.0, now)),
+        }
+    }
+
+    /// The colour of the row of a task, from how close its due is.
+    fn due_color(&self, task: &Value) -> Option<Color> {
+        if !self.color {
+            return None;
+        }
+
+        let due = task.get("due").and_then(utils::Due::from_api)?.0;
+        let now = chrono::Local::now().naive_local();
+
+        if due < now {
+            self.overdue_color.clone()
+        } else if due.date() == now.date() {
+            self.today_color.clone()
+        } else if due - now <= self.soon {
+            self.soon_color.clone()
+        } else {
+            None
+        }
+    }
+
+    /// The configured columns the tasks have, a table without due or group support
+    /// doesn't send the key at all.
+    fn task_columns<'a>(&self, tasks: impl Iterator<Item = &'a Value> + Clone) -> Vec<Field> {
+        let has = |key: &str| tasks.clone().any(|task| task.get(key).is_some());
+
+        self.columns
+            .iter()
+            .copied()
+            .filter(|column| match column {
+                Field::Due => has("due"),
+                Field::Group => has("group"),
+                Field::Id | Field::Description => true,
+            })
+            .collect()
+    }
+
+    /// The text of a task in a column.
+    fn cell(&self, task: &Value, column: Field) -> String {
+        let text = |key: &str| task.get(key).and_then(|v| v.as_str());
+
+        match column {
+            Field::Id => task
+                .get("id")
+                .and_then(|v| v.as_i64())
+                .unwrap_or(0)
+                .to_string(),
+            Field::Description => text("description").unwrap_or("N/A").to_string(),
+            Field::Due => self.due(task),
+            Field::Group => text("group").unwrap_or("N/A").to_string(),
+        }
+    }
 }
 
-#[derive(Tabled)]
-struct MinimalDataRow {
-    id: i32,
-    description: String,
+/// Parses colour names and 'bold', e.g. "bold green", "none" being no colour.
+fn parse_color(value: &str) -> Result<Option<Color>, String> {
+    let mut color = None;
+
+    for word in value.split_whitespace() {
+        let part = match word.to_lowercase().as_str() {
+            "none" => continue,
+            "bold" => Color::BOLD,
+            "black" => Color::FG_BLACK,
+            "red" => Color::FG_RED,
+            "green" => Color::FG_GREEN,
+            "yellow" => Color::FG_YELLOW,
+            "blue" => Color::FG_BLUE,
+            "magenta" => Color::FG_MAGENTA,
+            "cyan" => Color::FG_CYAN,
+            "white" => Color::FG_WHITE,
+            _ => {
+                return Err(format!(
+                    "Invalid config file: unknown colour '{word}', expected bold, black, red, \
+                     green, yellow, blue, magenta, cyan, white or none"
+                ))
+            }
+        };
+        color = Some(match color {
+            Some(color) => color | part,
+            None => part,
+        });
+    }
+
+    Ok(color)
 }
 
-impl_table_parsing!(FullDataRow {
-    description => "description",
-    due => "due",
-    group => "group",
-});
+fn column_name(column: Field) -> &'static str {
+    match column {
+        Field::Id => "id",
+        Field::Description => "description",
+        Field::Due => "due",
+        Field::Group => "group",
+    }
+}
 
-impl_table_parsing!(NoDueDataRow {
-    description => "description",
-    group => "group",
-});
+pub fn format_list_res(res: &Value, display: &Display) -> Option<Table> {
+    let arr = res.get("res").and_then(|v| v.as_array())?;
+    let columns = display.task_columns(arr.iter());
 
-impl_table_parsing!(NoGroupDataRow {
-    description => "description",
-    due => "due",
-});
+    let mut builder = Builder::default();
+    builder.push_record(columns.iter().map(|column| column_name(*column)));
+    for task in arr {
+        builder.push_record(columns.iter().map(|column| display.cell(task, *column)));
+    }
 
-impl_table_parsing!(MinimalDataRow {
-    description => "description",
-});
+    let id_column = columns.iter().position(|column| *column == Field::Id);
+    let mut table = style_table(builder.build(), id_column, display);
 
-pub fn format_list_res(res: &Value) -> Option<Table> {
-    if let Some(arr) = res.get("res").and_then(|v| v.as_array()) {
-        let has_due = arr.iter().any(|item| item.get("due").is_some());
-        let has_group = arr.iter().any(|item| item.get("group").is_some());
-
-        if has_due && has_group {
-            Some(to_table(arr, FullDataRow::from_json))
-        } else if has_due {
-            Some(to_table(arr, NoGroupDataRow::from_json))
-        } else if has_group {
-            Some(to_table(arr, NoDueDataRow::from_json))
-        } else {
-            Some(to_table(arr, MinimalDataRow::from_json))
+    // the header is the first row
+    for (i, task) in arr.iter().enumerate() {
+        if let Some(color) = display.due_color(task) {
+            table.modify(Rows::single(i + 1), color);
         }
-    } else {
-        None
     }
+
+    Some(table)
 }
 
 // since this is only this struct, it's manually implemented
@@ -87,45 +274,283 @@ struct SupportRow {
     due_support: String,
 }
 
-pub fn format_specs_res(res: &Value) -> Option<Table> {
+pub fn format_specs_res(res: &Value, display: &Display) -> Option<Table> {
     if let Some(arr) = res.get("res").and_then(|v| v.as_array()) {
-        return Some(to_table(arr, |item| SupportRow {
-            name: item
-                .get("name")
-                .and_then(|v| v.as_str())
-                .unwrap_or_default()
-                .to_string(),
-            group_support: item
-                .get("has_group")
-                .and_then(|v| v.as_bool())
-                .map(|b| if b { "Yes" } else { "No" })
-                .unwrap_or("No")
-                .to_string(),
-            due_support: item
-                .get("has_due")
-                .and_then(|v| v.as_bool())
-                .map(|b| if b { "Yes" } else { "No" })
-                .unwrap_or("No")
-                .to_string(),
-        }));
+        return Some(to_table(
+            arr,
+            |item| SupportRow {
+                name: item
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                group_support: item
+                    .get("has_group")
+                    .and_then(|v| v.as_bool())
+                    .map(|b| if b { "Yes" } else { "No" })
+                    .unwrap_or("No")
+                    .to_string(),
+                due_support: item
+                    .get("has_due")
+                    .and_then(|v| v.as_bool())
+                    .map(|b| if b { "Yes" } else { "No" })
+                    .unwrap_or("No")
+                    .to_string(),
+            },
+            display,
+        ));
     }
 
     // If no valid data is found (should never be the case)
     None
 }
 
-fn to_table<T: Tabled>(arr: &[Value], from_json_fn: impl Fn(&Value) -> T) -> Table {
+fn format_timestamp(value: &Value, display: &Display) -> Option<String> {
+    value
+        .as_str()
+        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+        .map(|dt| dt.format(&display.date_format).to_string())
+}
+
+/// Formats a single task as a vertical key/value card, only with the fields the task has.
+///
+/// The card is the detailed view, so unlike the tables it shows every field, those of
+/// 'columns' first and in that order, and the due both absolute and relative whatever 'dues' is.
+pub fn format_task_card(task: &Value, display: &Display) -> Table {
+    let mut builder = Builder::default();
+
+    let mut fields = display.columns.clone();
+    for field in [Field::Id, Field::Description, Field::Due, Field::Group] {
+        if !fields.contains(&field) {
+            fields.push(field);
+        }
+    }
+
+    for field in fields {
+        let value = match field {
+            Field::Id | Field::Description => display.cell(task, field),
+            // a table without due or group support doesn't send the key at all
+            Field::Due => match task.get("due") {
+                Some(due) => match utils::Due::from_api(due) {
+                    Some(due) => format!(
+                        "{} ({})",
+                        due.0.format(&display.date_format),
+                        utils::relative_due(due.0, chrono::Local::now().naive_local())
+                    ),
+                    None => "N/A".to_string(),
+                },
+                None => continue,
+            },
+            Field::Group if task.get("group").is_none() => continue,
+            Field::Group => display.cell(task, field),
+        };
+        builder.push_record([column_name(field).to_string(), value]);
+    }
+
+    for (key, label) in [("created_at", "created"), ("updated_at", "updated")] {
+        if let Some(timestamp) = task
+            .get(key)
+            .and_then(|value| format_timestamp(value, display))
+        {
+            builder.push_record([label.to_string(), timestamp]);
+        }
+    }
+
+    to_card(builder, display)
+}
+
+/// Formats the fields found in a quick-capture text, before the task is added.
+pub fn format_capture(capture: &Capture, display: &Display) -> Table {
+    let mut builder = Builder::default();
+
+    builder.push_record(["description".to_string(), capture.description.clone()]);
+    if let Some(due) = &capture.due {
+        let now = chrono::Local::now().naive_local();
+        builder.push_record([
+            "due".to_string(),
+            format!(
+                "{} ({})",
+                due.0.format(&display.date_format),
+                utils::relative_due(due.0, now)
+            ),
+        ]);
+    }
+    if let Some(group) = &capture.group {
+        builder.push_record(["group".to_string(), group.clone()]);
+    }
+    // there's nowhere to store them, the preview is the only place they show up
+    if !capture.tags.is_empty() {
+        builder.push_record([
+            "tags".to_string(),
+            format!("{} (not saved)", capture.tags.join(", ")),
+        ]);
+    }
+    if let Some(priority) = &capture.priority {
+        builder.push_record(["priority".to_string(), format!("{priority} (not saved)")]);
+    }
+
+    to_card(builder, display)
+}
+
+/// Renders key/value records as a card, keys on the left.
+fn to_card(mut builder: Builder, display: &Display) -> Table {
+    // these styles set the first row apart, it would look like the keys of the others
+    if matches!(display.style, TableStyle::Markdown | TableStyle::Psql) {
+        builder.insert_record(0, ["FIELD", "VALUE"]);
+    }
+
+    let mut table = builder.build();
+    table.with(display.theme()).modify(
+        Columns::first(),
+        Format::content(|text| text.to_uppercase()),
+    );
+
+    if let Some(color) = display.header_color.clone().filter(|_| display.color) {
+        table.modify(Columns::first(), color);
+    }
+    display.fit(&mut table);
+
+    table
+}
+
+/// Wraps every match of `re` in `text` with the highlight colour.
+fn highlight(text: &str, re: &Regex, display: &Display) -> String {
+    if !display.color {
+        return text.to_string();
+    }
+    let color = Color::BOLD | Color::FG_YELLOW;
+
+    re.replace_all(text, |caps: &regex::Captures| {
+        format!("{}{}{}", color.get_prefix(), &caps[0], color.get_suffix())
+    })
+    .into_owned()
+}
+
+/// Formats search hits, given as `(tablename, task)` pairs, highlighting the matches of `re`.
+pub fn format_search_res(hits: &[(String, Value)], re: &Regex, display: &Display) -> Option<Table> {
+    if hits.is_empty() {
+        return None;
+    }
+
+    // the tasks of every table, so the columns of any of them
+    let columns = display.task_columns(hits.iter().map(|(_, task)| task));
+
+    let mut builder = Builder::default();
+    builder.push_record(
+        std::iter::once("table").chain(columns.iter().map(|column| column_name(*column))),
+    );
+    for (tablename, task) in hits {
+        let cells = columns.iter().map(|column| {
+            let cell = display.cell(task, *column);
+            // the placeholder of a missing value isn't part of the task
+            let value = match column {
+                Field::Description => task.get("description"),
+                Field::Group => task.get("group"),
+                Field::Id | Field::Due => None,
+            };
+            if value.is_some_and(Value::is_string) {
+                highlight(&cell, re, display)
+            } else {
+                cell
+            }
+        });
+        builder.push_record(std::iter::once(tablename.clone()).chain(cells));
+    }
+
+    let id_column = columns
+        .iter()
+        .position(|column| *column == Field::Id)
+        .map(|i| i + 1);
+    Some(style_table(builder.build(), id_column, display))
+}
+
+#[derive(Tabled)]
+struct JournalRow {
+    n: usize,
+    date: String,
+    operation: String,
+    table: String,
+    tasks: usize,
+}
+
+pub fn format_journal(entries: &[journal::Entry], display: &Display) -> Option<Table> {
+    if entries.is_empty() {
+        return None;
+    }
+
+    // most recent first, numbered the way `rsm undo N` expects
+    let numbered: Vec<(usize, &journal::Entry)> = entries.iter().rev().enumerate().collect();
+
+    Some(to_table(
+        &numbered,
+        |(i, entry)| JournalRow {
+            n: i + 1,
+            date: entry.timestamp.format(&display.date_format).to_string(),
+            operation: entry.operation.to_string(),
+            table: match &entry.destination {
+                Some(destination) => format!("{} -> {destination}", entry.tablename),
+                None => entry.tablename.clone(),
+            },
+            tasks: entry.tasks.len(),
+        },
+        display,
+    ))
+}
+
+fn to_table<I, T: Tabled>(arr: &[I], from_json_fn: impl Fn(&I) -> T, display: &Display) -> Table {
     let rows: Vec<T> = arr.iter().map(from_json_fn).collect();
 
-    Table::new(&rows)
-        .with(Style::modern_rounded())
-        .with(Colorization::exact(
-            [Color::BOLD | Color::FG_GREEN],
-            Rows::first(),
-        ))
-        .modify(Rows::new(1..), Width::wrap(110).keep_words(true))
-        .modify(Rows::first(), Format::content(|text| text.to_uppercase()))
-        .modify(Columns::first().not(Rows::first()), Color::BOLD)
-        .modify(Columns::first().not(Rows::first()), Alignment::right())
-        .to_owned()
+    style_table(Table::new(&rows), Some(0), display)
+}
+
+/// Applies the theme to a table whose first row is the header, `id_column` being right aligned.
+fn style_table(mut table: Table, id_column: Option<usize>, display: &Display) -> Table {
+    table
+        .with(display.theme())
+        .modify(Rows::first(), Format::content(|text| text.to_uppercase()));
+
+    if let Some(id_column) = id_column {
+        table.modify(
+            Columns::single(id_column).not(Rows::first()),
+            Alignment::right(),
+        );
+    }
+
+    if display.color {
+        if let Some(color) = &display.header_color {
+            table.with(Colorization::exact([color.clone()], Rows::first()));
+        }
+        if let (Some(id_column), Some(color)) = (id_column, &display.id_color) {
+            table.modify(Columns::single(id_column).not(Rows::first()), color.clone());
+        }
+    }
+    display.fit(&mut table);
+
+    table
+}
+
+/// Writes `output` to `$PAGER`, `less` by default, an empty `$PAGER` or `cat` turns paging off.
+fn page(output: &str) -> Result<(), String> {
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+    let args = shlex::split(&pager)
+        .filter(|args| !args.is_empty() && args[0] != "cat")
+        .ok_or_else(|| "No pager".to_string())?;
+
+    let mut command = Command::new(&args[0]);
+    command.args(&args[1..]).stdin(Stdio::piped());
+    // like git, keeps the colours and exits when the output fits after all
+    if std::env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Couldn't run the pager '{pager}': {e}"))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // the pager can be quit before reading everything
+        let _ = stdin.write_all(output.as_bytes());
+    }
+    child.wait().map_err(|e| format!("Internal error: {e}"))?;
+
+    Ok(())
 }
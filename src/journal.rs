@@ -0,0 +1,309 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Write},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    api::Api,
+    utils::{self, Due},
+};
+
+const JOURNAL_FILE: &str = ".undo_journal";
+// older entries are dropped once the journal grows past this
+const MAX_ENTRIES: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
+    Remove,
+    Clear,
+    Drop,
+    Update,
+    Move,
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operation::Remove => write!(f, "remove"),
+            Operation::Clear => write!(f, "clear"),
+            Operation::Drop => write!(f, "drop"),
+            Operation::Update => write!(f, "update"),
+            Operation::Move => write!(f, "move"),
+        }
+    }
+}
+
+/// The state of a table right before a destructive operation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub timestamp: chrono::DateTime<chrono::Local>,
+    pub operation: Operation,
+    pub tablename: String,
+    // only needed to re-create a dropped table
+    #[serde(default)]
+    pub has_due: bool,
+    #[serde(default)]
+    pub has_group: bool,
+    // only for a move, the table the tasks went to and their ids there, in the order of `tasks`
+    #[serde(default)]
+    pub destination: Option<String>,
+    #[serde(default)]
+    pub destination_ids: Vec<u64>,
+    /// Tasks as returned by the api before the operation
+    pub tasks: Vec<Value>,
+}
+
+impl Entry {
+    pub fn new(operation: Operation, tablename: &str, tasks: Vec<Value>) -> Self {
+        Entry {
+            timestamp: chrono::Local::now(),
+            operation,
+            tablename: tablename.to_string(),
+            has_due: false,
+            has_group: false,
+            destination: None,
+            destination_ids: vec![],
+            tasks,
+        }
+    }
+}
+
+/// Loads the journal, oldest entry first.
+pub fn load() -> Vec<Entry> {
+    fs::read_to_string(utils::data_path(JOURNAL_FILE))
+        .map(|content| {
+            content
+                .lines()
+                .filter_map(|line| serde_json::from_str(line).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn save(entries: &[Entry]) -> io::Result<()> {
+    let mut file = fs::File::create(utils::data_path(JOURNAL_FILE))?;
+
+    for entry in entries {
+        let line = serde_json::to_string(entry)?;
+        writeln!(file, "{line}")?;
+    }
+
+    Ok(())
+}
+
+/// Appends an entry to the journal, nothing is recorded if there is nothing to restore.
+pub fn record(entry: Entry) -> Result<(), String> {
+    if entry.tasks.is_empty() && entry.operation != Operation::Drop {
+        return Ok(());
+    }
+
+    let mut entries = load();
+    entries.push(entry);
+
+    let overflow = entries.len().saturating_sub(MAX_ENTRIES);
+    entries.drain(..overflow);
+
+    save(&entries).map_err(|e| format!("Internal error: couldn't write the undo journal: {e}"))
+}
+
+/// Returns the `n`th most recent entry (starting from 1), it stays in the journal.
+pub fn get(n: usize) -> Result<Entry, String> {
+    let mut entries = load();
+
+    if n == 0 || n > entries.len() {
+        return Err(format!(
+            "No operation number {n} in the undo journal ({} recorded)",
+            entries.len()
+        ));
+    }
+
+    Ok(entries.remove(entries.len() - n))
+}
+
+/// Keeps in `entry` only the tasks at the `unrestored` positions, so undoing it again
+/// retries them, or removes it from the journal once everything is restored.
+pub fn settle(entry: &Entry, unrestored: &[usize]) -> Result<(), String> {
+    if unrestored.is_empty() {
+        return discard(entry);
+    }
+
+    let mut entries = load();
+
+    if let Some(kept) = entries.iter_mut().rev().find(|e| *e == entry) {
+        kept.tasks = unrestored.iter().map(|&i| entry.tasks[i].clone()).collect();
+        if !entry.destination_ids.is_empty() {
+            kept.destination_ids = unrestored
+                .iter()
+                .map(|&i| entry.destination_ids[i])
+                .collect();
+        }
+        // a dropped table is back, what's left is re-adding its tasks
+        if kept.operation == Operation::Drop {
+            kept.operation = Operation::Clear;
+        }
+    }
+
+    save(&entries).map_err(|e| format!("Internal error: couldn't write the undo journal: {e}"))
+}
+
+/// Removes `entry` from the journal, once it has been restored.
+pub fn discard(entry: &Entry) -> Result<(), String> {
+    let mut entries = load();
+
+    if let Some(pos) = entries.iter().rposition(|e| e == entry) {
+        entries.remove(pos);
+    }
+
+    save(&entries).map_err(|e| format!("Internal error: couldn't write the undo journal: {e}"))
+}
+
+fn tasks(api: &Api, tablename: &str) -> Result<Vec<Value>, String> {
+    let mut res = api.list_table_contents(tablename, None, None)?;
+
+    Ok(match res.get_mut("res").map(Value::take) {
+        Some(Value::Array(tasks)) => tasks,
+        _ => vec![],
+    })
+}
+
+pub fn task_ids(api: &Api, tablename: &str) -> Result<HashSet<u64>, String> {
+    Ok(tasks(api, tablename)?
+        .iter()
+        .filter_map(|task| task.get("id").and_then(|v| v.as_u64()))
+        .collect())
+}
+
+/// Ids of the tasks added to `tablename` since `existing` was taken, in the order of the
+/// adds, since the backend hands out increasing ids.
+pub fn new_ids(api: &Api, tablename: &str, existing: &HashSet<u64>) -> Result<Vec<u64>, String> {
+    let mut new_ids: Vec<u64> = task_ids(api, tablename)?
+        .difference(existing)
+        .copied()
+        .collect();
+    new_ids.sort_unstable();
+
+    Ok(new_ids)
+}
+
+/// What `restore` did with the tasks of an entry.
+pub struct Restored {
+    /// Ids that changed and errors, a line each
+    pub report: Vec<String>,
+    /// Positions in the entry's tasks of the ones that couldn't be restored
+    pub unrestored: Vec<usize>,
+}
+
+/// Restores the state recorded in `entry` through the api.
+///
+/// Moved tasks are re-added to the source table, then removed from the destination one.
+/// An updated task that gained a due or a group is re-added without it, then the updated
+/// one is removed. Re-added tasks get new ids from the backend, every task whose id
+/// changed is reported, as well as the errors. A task is only gone from where it was
+/// once its copy is back, so the unrestored ones can be retried.
+pub fn restore(api: &Api, entry: &Entry) -> Result<Restored, String> {
+    let tablename = entry.tablename.as_str();
+    let mut report = vec![];
+    let mut unrestored = vec![];
+
+    // tasks to re-add, with the copy to remove once they're back
+    let mut to_add: Vec<(usize, Option<(&str, u64)>)> = vec![];
+    if entry.operation == Operation::Update {
+        let current = tasks(api, tablename)?;
+
+        // updated tasks still exist, so their ids are kept
+        for (i, task) in entry.tasks.iter().enumerate() {
+            let id = task.get("id").and_then(|v| v.as_u64()).unwrap_or_default();
+
+            // a null field was absent before the update, but the api can't unset a field,
+            // so a task that got a due or a group since is re-added without it
+            let now = current
+                .iter()
+                .find(|t| t.get("id").and_then(|v| v.as_u64()) == Some(id));
+            let added = |field: &str| {
+                task.get(field).is_none_or(Value::is_null)
+                    && now.and_then(|t| t.get(field)).is_some_and(|v| !v.is_null())
+            };
+            if added("due") || added("group") {
+                to_add.push((i, Some((tablename, id))));
+                continue;
+            }
+
+            if let Err(e) = api.update_task(
+                tablename,
+                id as usize,
+                task.get("description").and_then(|v| v.as_str()),
+                task.get("due").and_then(Due::from_api),
+                task.get("group").and_then(|v| v.as_str()),
+            ) {
+                report.push(format!("Error when restoring task with id {id}: {e}"));
+                unrestored.push(i);
+            }
+        }
+    } else if let Some(destination) = entry.destination.as_deref() {
+        to_add.extend(
+            entry
+                .destination_ids
+                .iter()
+                .enumerate()
+                .map(|(i, id)| (i, Some((destination, *id)))),
+        );
+    } else {
+        if entry.operation == Operation::Drop {
+            api.create_table(tablename, entry.has_due, entry.has_group)?;
+        }
+        to_add.extend((0..entry.tasks.len()).map(|i| (i, None)));
+    }
+
+    if to_add.is_empty() {
+        return Ok(Restored { report, unrestored });
+    }
+
+    let existing = task_ids(api, tablename)?;
+
+    let mut restored = vec![];
+    for (i, stale) in to_add {
+        let task = &entry.tasks[i];
+        let id = task.get("id").and_then(|v| v.as_u64()).unwrap_or_default();
+
+        match api.add_task(
+            tablename,
+            task.get("description")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default(),
+            task.get("due").and_then(Due::from_api),
+            task.get("group").and_then(|v| v.as_str()),
+        ) {
+            Ok(_) => restored.push((id, stale)),
+            Err(e) => {
+                report.push(format!("Error when restoring task with id {id}: {e}"));
+                unrestored.push(i);
+            }
+        }
+    }
+
+    for ((old_id, stale), new_id) in restored
+        .into_iter()
+        .zip(new_ids(api, tablename, &existing)?)
+    {
+        if old_id != new_id {
+            report.push(format!("Task with id {old_id} is now id {new_id}"));
+        }
+
+        // the task is back, a copy left behind is a duplicate rather than a loss
+        if let Some((table, id)) = stale {
+            if let Err(e) = api.remove_task(table, id as usize) {
+                report.push(format!(
+                    "Error when removing task with id {id} from '{table}', remove it by hand: {e}"
+                ));
+            }
+        }
+    }
+
+    unrestored.sort_unstable();
+    Ok(Restored { report, unrestored })
+}
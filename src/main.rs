@@ -1,10 +1,32 @@
 use api::Api;
-use clap::{error::Result, Args, Parser, Subcommand};
-use formatter::{format_list_res, format_specs_res};
-use utils::{parse_due, prompt_logout, Due};
+use clap::{error::Result, Args, CommandFactory, Parser, Subcommand};
+use clap_complete::{ArgValueCandidates, CompleteEnv};
+use config::{Config, DisplayConfig};
+use filter::{parse_filter, parse_sort, Filter, SortSpec};
+use formatter::{
+    format_capture, format_journal, format_list_res, format_search_res, format_specs_res,
+    format_task_card, Display,
+};
+use journal::Operation;
+use std::{
+    io::IsTerminal,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+use utils::{confirm, parse_due, prompt_logout, Due, IdSelection, TableSpec};
 
 mod api;
+mod cache;
+mod capture;
+mod complete;
+mod config;
+mod editor;
+mod filter;
 mod formatter;
+mod journal;
+mod man;
+mod picker;
+mod shell;
+mod tui;
 mod utils;
 
 #[derive(Parser, Debug)]
@@ -15,6 +37,15 @@ mod utils;
     arg_required_else_help = true
 )]
 struct Cli {
+    /// Skip the confirmation prompt of destructive commands
+    #[arg(short = 'y', long = "yes", global = true)]
+    yes: bool,
+    /// Print without colours, like setting NO_COLOR
+    #[arg(long = "no-color", global = true)]
+    no_color: bool,
+    /// Print long tables directly instead of through $PAGER
+    #[arg(long = "no-pager", global = true)]
+    no_pager: bool,
     #[command(subcommand)]
     command: Commands,
 }
@@ -37,10 +68,47 @@ enum Commands {
     Add(AddArgs),
     /// Removes a task from a table
     Remove(RemoveArgs),
-    /// Updates a task from a table
+    /// Updates one or more tasks from a table
     Update(UpdateArgs),
+    /// Marks tasks as done, which removes them from the table
+    Done(RemoveArgs),
     /// Clears completely a table
     Clear(ClearArgs),
+    /// Reverts a remove, clear, drop, update or move
+    Undo(UndoArgs),
+    /// Searches tasks by description and group across tables
+    Search(SearchArgs),
+    /// Shows every field of a single task
+    Show(ShowArgs),
+    /// Edits a task, or a whole table, in your $EDITOR
+    Edit(EditArgs),
+    /// Moves tasks to another table
+    Move(TransferArgs),
+    /// Copies tasks to another table
+    Copy(TransferArgs),
+    /// Opens an interactive shell to run several commands in a row
+    Shell,
+    /// Opens a full-screen interface to browse and triage the tables
+    Tui,
+    /// Renames or reconfigures a table
+    #[command(subcommand)]
+    Table(TableCommands),
+    /// Prints the shell script that enables completions
+    Completions(CompletionsArgs),
+    /// Renders the man pages
+    Man(ManArgs),
+}
+
+#[derive(Subcommand, Debug)]
+enum TableCommands {
+    /// Renames a table, keeping its tasks
+    Rename(RenameArgs),
+    /// Enables or disables the due and group support of a table, keeping its tasks
+    Alter(AlterArgs),
+    /// Shows or sets the table of the commands given none
+    Default(DefaultArgs),
+    /// Lists, sets or removes the aliases of the tables
+    Alias(AliasArgs),
 }
 
 // create table
@@ -65,63 +133,402 @@ struct CreateArgs {
 // drop table
 #[derive(Args, Debug)]
 struct DropArgs {
+    #[arg(add = ArgValueCandidates::new(complete::tables))]
     tablename: String,
 }
 
 // list table items or all tables with specs if no tablename provided
 #[derive(Args, Debug)]
 struct ListArgs {
+    #[arg(add = ArgValueCandidates::new(complete::tables))]
     tablename: Option<String>,
-    #[arg(short = 'g', long = "group", requires = "tablename")]
+    #[arg(
+        short = 'T',
+        long = "tables",
+        conflicts_with = "tablename",
+        help = "list the tables with their specs, even when there is a default table"
+    )]
+    tables: bool,
+    #[arg(short = 'g', long = "group", add = ArgValueCandidates::new(complete::groups))]
     group: Option<String>,
-    // can be either due or id, checked in the backend
-    #[arg(short = 's', long = "sort-by", requires = "tablename")]
-    sort_by: Option<String>,
+    #[arg(short = 's', long = "sort-by", value_parser = parse_sort,
+        help = "comma separated keys among id, description, due, group, each optionally followed by ':asc' or ':desc' (e.g. 'due:desc,id')")]
+    sort_by: Option<SortSpec>,
+    #[arg(short = 'w', long = "where", value_parser = parse_filter,
+        help = "filter expression, e.g. 'due < tomorrow and group != home and description ~ \"deploy\"'")]
+    filter: Option<Filter>,
 }
 
 // add a task to a table
 #[derive(Args, Debug)]
 struct AddArgs {
-    tablename: String,
-    #[arg(short = 't', long = "task", requires = "tablename")]
-    task: String,
-    #[arg(short = 'd', long = "due", requires = "tablename", value_parser = parse_due, help = "due in the format of 'hh:mm' or 'YYYY-MM-dd hh:mm'")]
+    #[arg(add = ArgValueCandidates::new(complete::tables))]
+    tablename: Option<String>,
+    #[arg(num_args = 1.., conflicts_with = "task",
+        help = "the task, with inline '#group', '@tag' and '!priority' (only previewed), 'due:<date>' or a trailing date (e.g. 'call bob tomorrow 10:00 #calls'), '-' reads many tasks from stdin")]
+    words: Vec<String>,
+    #[arg(short = 't', long = "task", help = "the task, taken as is")]
+    task: Option<String>,
+    #[arg(short = 'd', long = "due", value_parser = parse_due, help = "due in the format of 'hh:mm' or 'YYYY-MM-dd hh:mm'")]
     due: Option<Due>,
-    #[arg(short = 'g', long = "group", requires = "tablename")]
+    #[arg(short = 'g', long = "group", add = ArgValueCandidates::new(complete::groups))]
     group: Option<String>,
+    #[arg(short = 'f', long = "from-file", conflicts_with_all = ["words", "task"],
+        help = "add a task per line of the file, either a quick-capture text or a JSON object with description, due and group")]
+    from_file: Option<std::path::PathBuf>,
+    #[arg(
+        long = "raw",
+        conflicts_with = "task",
+        help = "don't look for inline fields in the task"
+    )]
+    raw: bool,
+    #[arg(
+        long = "stop-on-error",
+        help = "with many tasks, stop at the first invalid or failed one instead of skipping it"
+    )]
+    stop_on_error: bool,
+    #[arg(
+        short = 'n',
+        long = "dry-run",
+        help = "only show the fields of the task, without adding it"
+    )]
+    dry_run: bool,
 }
 
-// remove a task from a table
+// the grammar of 'utils::parse_ids', detailed in the ID SELECTION section of the man page
+const IDS_HELP: &str = "IDs can be single (42), lists (42 43 or 1,2,5), ranges (10..15, 10.., ..5), all, last, overdue or group:<name>, '^' excludes (1..20 ^7). Combinations allowed.";
+
+// remove tasks from a table, or mark them as done
 #[derive(Args, Debug)]
 struct RemoveArgs {
-    tablename: String,
-    #[arg(requires = "tablename", value_parser = utils::parse_ids, num_args = 1..,
-        help = "IDs can be single (42), multiple (42 43), or ranges (10..15). Combinations allowed.")]
-    ids: Vec<Vec<usize>>,
+    #[arg(add = ArgValueCandidates::new(complete::tables))]
+    tablename: Option<String>,
+    #[arg(value_parser = utils::parse_ids, num_args = 1..,
+        add = ArgValueCandidates::new(complete::ids),
+        help = IDS_HELP)]
+    ids: Vec<IdSelection>,
+    #[arg(
+        long = "pick",
+        conflicts_with = "ids",
+        help = "pick the tasks with a fuzzy finder instead of typing their ids"
+    )]
+    pick: bool,
+    #[arg(short = 'g', long = "group", conflicts_with_all = ["ids", "pick"],
+        help = "the tasks of this group", add = ArgValueCandidates::new(complete::groups))]
+    group: Option<String>,
+    #[arg(long = "due-before", conflicts_with_all = ["ids", "pick"], value_parser = filter::parse_due_before,
+        help = "the tasks due before this date (today, tomorrow, 'YYYY-MM-dd' or 'YYYY-MM-dd hh:mm')")]
+    due_before: Option<Filter>,
+    #[arg(short = 'm', long = "match", conflicts_with_all = ["ids", "pick"], value_parser = filter::parse_match,
+        help = "the tasks whose description matches this regex, case insensitive")]
+    matches: Option<Filter>,
 }
 
 // update a task of a table
 #[derive(Args, Debug)]
 struct UpdateArgs {
-    tablename: String,
-    #[arg(requires = "tablename")]
-    id: String,
-    #[arg(short = 't', long = "task", requires = "id")]
+    #[arg(add = ArgValueCandidates::new(complete::tables))]
+    tablename: Option<String>,
+    #[arg(value_parser = utils::parse_ids, num_args = 1.., add = ArgValueCandidates::new(complete::ids),
+        help = IDS_HELP)]
+    ids: Vec<IdSelection>,
+    #[arg(
+        long = "pick",
+        conflicts_with = "ids",
+        help = "pick the tasks with a fuzzy finder instead of typing their ids"
+    )]
+    pick: bool,
+    #[arg(short = 't', long = "task")]
     task: Option<String>,
-    #[arg(short = 'd', long = "due", requires = "tablename", value_parser = parse_due, help = "due in the format of 'hh:mm' or 'YYYY-MM-dd hh:mm'")]
+    #[arg(short = 'd', long = "due", value_parser = parse_due, help = "due in the format of 'hh:mm' or 'YYYY-MM-dd hh:mm'")]
     due: Option<Due>,
-    #[arg(short = 'g', long = "group", requires = "id")]
+    #[arg(short = 'g', long = "group", add = ArgValueCandidates::new(complete::groups))]
     group: Option<String>,
 }
 
 // clean a table
 #[derive(Args, Debug)]
 struct ClearArgs {
+    #[arg(add = ArgValueCandidates::new(complete::tables))]
+    tablename: Option<String>,
+}
+
+// undo a destructive operation
+#[derive(Args, Debug)]
+struct UndoArgs {
+    #[arg(
+        short = 'l',
+        long = "list",
+        conflicts_with = "n",
+        help = "list the recorded operations"
+    )]
+    list: bool,
+    #[arg(help = "operation to undo, as numbered by '--list' (defaults to the last one)")]
+    n: Option<usize>,
+}
+
+// search tasks in every table, or only in one
+#[derive(Args, Debug)]
+struct SearchArgs {
+    query: String,
+    #[arg(long = "table", help = "only search in this table", add = ArgValueCandidates::new(complete::tables))]
+    table: Option<String>,
+    #[arg(
+        short = 'r',
+        long = "regex",
+        help = "treat the query as a regular expression"
+    )]
+    regex: bool,
+    #[arg(short = 'c', long = "case-sensitive")]
+    case_sensitive: bool,
+}
+
+// show a single task in detail
+#[derive(Args, Debug)]
+struct ShowArgs {
+    #[arg(add = ArgValueCandidates::new(complete::tables))]
+    tablename: Option<String>,
+    #[arg(add = ArgValueCandidates::new(complete::ids))]
+    id: Option<usize>,
+}
+
+// edit a task in the user's editor
+#[derive(Args, Debug)]
+struct EditArgs {
+    #[arg(add = ArgValueCandidates::new(complete::tables))]
+    tablename: Option<String>,
+    #[arg(help = "task to edit, the whole table is edited if omitted",
+        add = ArgValueCandidates::new(complete::ids))]
+    id: Option<usize>,
+}
+
+// move or copy tasks from a table to another
+#[derive(Args, Debug)]
+struct TransferArgs {
+    #[arg(add = ArgValueCandidates::new(complete::tables))]
+    from: String,
+    #[arg(required = true, value_parser = utils::parse_ids, num_args = 1..,
+        add = ArgValueCandidates::new(complete::ids),
+        help = IDS_HELP)]
+    ids: Vec<IdSelection>,
+    #[arg(add = ArgValueCandidates::new(complete::tables))]
+    to: String,
+    #[arg(
+        short = 'g',
+        long = "group",
+        help = "group to give the tasks in the destination table"
+    )]
+    group: Option<String>,
+}
+
+// rename a table
+#[derive(Args, Debug)]
+struct RenameArgs {
+    #[arg(add = ArgValueCandidates::new(complete::tables))]
+    old: String,
+    new: String,
+}
+
+// change the specs of a table
+#[derive(Args, Debug)]
+struct AlterArgs {
+    #[arg(add = ArgValueCandidates::new(complete::tables))]
     tablename: String,
+    #[arg(long = "enable-due", conflicts_with = "disable_due")]
+    enable_due: bool,
+    #[arg(long = "disable-due")]
+    disable_due: bool,
+    #[arg(long = "enable-group", conflicts_with = "disable_group")]
+    enable_group: bool,
+    #[arg(long = "disable-group")]
+    disable_group: bool,
+}
+
+// default table
+#[derive(Args, Debug)]
+struct DefaultArgs {
+    #[arg(add = ArgValueCandidates::new(complete::tables))]
+    tablename: Option<String>,
+    #[arg(long = "unset", conflicts_with = "tablename")]
+    unset: bool,
+}
+
+// table aliases
+#[derive(Args, Debug)]
+struct AliasArgs {
+    alias: Option<String>,
+    #[arg(requires = "alias", add = ArgValueCandidates::new(complete::tables))]
+    tablename: Option<String>,
+    #[arg(
+        short = 'r',
+        long = "remove",
+        requires = "alias",
+        conflicts_with = "tablename"
+    )]
+    remove: bool,
+}
+
+// shell completions
+#[derive(Args, Debug)]
+struct CompletionsArgs {
+    #[arg(value_parser = complete::SHELLS)]
+    shell: String,
+}
+
+// man pages
+#[derive(Args, Debug)]
+struct ManArgs {
+    #[arg(
+        long = "out-dir",
+        short = 'o',
+        help = "write the pages of rsm and every command into this directory, instead of printing the rsm one"
+    )]
+    out_dir: Option<std::path::PathBuf>,
+}
+
+/// Fills in the table of the commands given none, and replaces the aliases by the tables
+/// they stand for.
+///
+/// `default` is the table used when it's left out. As tables aren't known while parsing,
+/// with a default table the first argument is taken as the ids when it reads as ids
+/// (a single id for 'show' and 'edit'), and as the task when it's the only one of 'add'.
+fn resolve_tables(command: &mut Commands, config: &Config, default: Option<&str>) {
+    if let Some(default) = default {
+        let reads_as_id = |tablename: &Option<String>| {
+            tablename
+                .as_deref()
+                .and_then(|arg| arg.parse::<usize>().ok())
+        };
+
+        match command {
+            Commands::Add(AddArgs {
+                tablename,
+                words,
+                task: None,
+                from_file: None,
+                ..
+            }) if words.is_empty() => {
+                words.extend(tablename.take());
+            }
+            Commands::Remove(RemoveArgs { tablename, ids, .. })
+            | Commands::Done(RemoveArgs { tablename, ids, .. })
+            | Commands::Update(UpdateArgs { tablename, ids, .. }) => {
+                if let Some(first) = tablename
+                    .as_deref()
+                    .and_then(|arg| utils::parse_ids(arg).ok())
+                {
+                    ids.insert(0, first);
+                    *tablename = None;
+                }
+            }
+            Commands::Show(ShowArgs {
+                tablename,
+                id: id @ None,
+            })
+            | Commands::Edit(EditArgs {
+                tablename,
+                id: id @ None,
+            }) => {
+                if let Some(first) = reads_as_id(tablename) {
+                    *id = Some(first);
+                    *tablename = None;
+                }
+            }
+            _ => {}
+        }
+
+        match command {
+            Commands::List(ListArgs {
+                tablename,
+                tables: false,
+                ..
+            })
+            | Commands::Add(AddArgs { tablename, .. })
+            | Commands::Remove(RemoveArgs { tablename, .. })
+            | Commands::Done(RemoveArgs { tablename, .. })
+            | Commands::Update(UpdateArgs { tablename, .. })
+            | Commands::Clear(ClearArgs { tablename })
+            | Commands::Show(ShowArgs { tablename, .. })
+            | Commands::Edit(EditArgs { tablename, .. }) => {
+                tablename.get_or_insert_with(|| default.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    let tablenames: Vec<&mut String> = match command {
+        Commands::Drop(DropArgs { tablename })
+        | Commands::Table(TableCommands::Alter(AlterArgs { tablename, .. })) => vec![tablename],
+        Commands::List(ListArgs { tablename, .. })
+        | Commands::Add(AddArgs { tablename, .. })
+        | Commands::Remove(RemoveArgs { tablename, .. })
+        | Commands::Done(RemoveArgs { tablename, .. })
+        | Commands::Update(UpdateArgs { tablename, .. })
+        | Commands::Clear(ClearArgs { tablename })
+        | Commands::Show(ShowArgs { tablename, .. })
+        | Commands::Edit(EditArgs { tablename, .. })
+        | Commands::Table(TableCommands::Default(DefaultArgs { tablename, .. }))
+        | Commands::Table(TableCommands::Alias(AliasArgs { tablename, .. })) => {
+            tablename.iter_mut().collect()
+        }
+        Commands::Search(SearchArgs { table, .. }) => table.iter_mut().collect(),
+        Commands::Move(TransferArgs { from, to, .. })
+        | Commands::Copy(TransferArgs { from, to, .. }) => vec![from, to],
+        Commands::Table(TableCommands::Rename(RenameArgs { old, .. })) => vec![old],
+        // a new table can't be an alias yet
+        _ => vec![],
+    };
+
+    for tablename in tablenames {
+        *tablename = config.resolve(tablename);
+    }
+}
+
+/// The table of a command, see `resolve_tables`.
+fn table(tablename: Option<String>) -> Result<String, String> {
+    tablename.ok_or_else(|| {
+        "No table given and no default table set, see 'rsm table default'".to_string()
+    })
 }
 
 fn main() -> Result<(), String> {
-    let cli = Cli::parse();
+    // answers the completion requests of the shell scripts, before anything is printed
+    CompleteEnv::with_factory(Cli::command).complete();
+
+    let mut cli = Cli::parse();
+    let config = Config::load()?;
+    resolve_tables(&mut cli.command, &config, config.default_table.as_deref());
+
+    // completions don't need the api
+    if let Commands::Completions(CompletionsArgs { shell }) = &cli.command {
+        // register for the name the user typed, e.g. 'rsm' when installed with install.sh
+        let bin = std::env::args()
+            .next()
+            .and_then(|arg0| {
+                std::path::Path::new(&arg0)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| "rsm".to_string());
+
+        return complete::write_registration(shell, Cli::command().get_name(), &bin);
+    }
+
+    // neither do the man pages, they're named after the installed 'rsm' binary
+    if let Commands::Man(ManArgs { out_dir }) = &cli.command {
+        let cmd = Cli::command().name("rsm").bin_name("rsm");
+
+        return match out_dir {
+            Some(out_dir) => {
+                for path in man::generate_to(cmd, out_dir)? {
+                    println!("{path}");
+                }
+                Ok(())
+            }
+            None => man::print(cmd),
+        };
+    }
+
     let api = Api::from_token_file();
 
     api.has_connection()?
@@ -187,7 +594,20 @@ fn main() -> Result<(), String> {
         );
     }
 
-    // Now process remaining commands
+    execute(&api, cli, &config)
+}
+
+/// Runs a command that requires to be logged in, from the command line or from the shell.
+fn execute(api: &Api, cli: Cli, config: &Config) -> Result<(), String> {
+    // a bad '[display]' value mustn't stop the commands, e.g. the one to fix it
+    let display = config
+        .display_config()
+        .and_then(|display| Display::new(&display, cli.no_color, cli.no_pager))
+        .or_else(|e| {
+            eprintln!("Warning: {e}, the default display is used");
+            Display::new(&DisplayConfig::default(), cli.no_color, cli.no_pager)
+        })?;
+
     match cli.command {
         Commands::Logout => {
             let logout = prompt_logout().map_err(|e| format!("Internal error: {e}"))?;
@@ -205,6 +625,7 @@ fn main() -> Result<(), String> {
                 if let Err(e) = std::fs::File::create(".token") {
                     panic!("couldnt clear token file: {e}")
                 }
+                cache::invalidate();
             }
 
             println!();
@@ -218,6 +639,7 @@ fn main() -> Result<(), String> {
             group,
         }) => {
             let res = api.create_table(&tablename, due, group)?;
+            cache::invalidate();
 
             let formatted_res = res
                 .get("res")
@@ -230,7 +652,27 @@ fn main() -> Result<(), String> {
             Ok(())
         }
         Commands::Drop(DropArgs { tablename }) => {
+            // the tasks are fetched even with '--yes', 'rsm undo' needs them
+            let spec = fetch_table_spec(api, &tablename)?;
+            let tasks = fetch_tasks(api, &tablename)?;
+            if !cli.yes {
+                let question = format!(
+                    "This will drop the table '{tablename}' and its {} task(s), continue?",
+                    tasks.len()
+                );
+                if !confirm(&question, false)? {
+                    println!("Aborted.");
+                    return Ok(());
+                }
+            }
+
             let res = api.drop_table(&tablename)?;
+            cache::invalidate();
+            journal::record(journal::Entry {
+                has_due: spec.has_due,
+                has_group: spec.has_group,
+                ..journal::Entry::new(Operation::Drop, &tablename, tasks)
+            })?;
 
             let formatted_res = res
                 .get("res")
@@ -243,16 +685,30 @@ fn main() -> Result<(), String> {
             Ok(())
         }
         Commands::List(list_args) => {
-            if let Some(tablename) = list_args.tablename {
+            let filtered = list_args.group.is_some()
+                || list_args.sort_by.is_some()
+                || list_args.filter.is_some();
+            if filtered && (list_args.tablename.is_none() || list_args.tables) {
+                return Err("'--group', '--sort-by' and '--where' need a table to list".to_string());
+            }
+
+            if let Some(tablename) = list_args.tablename.filter(|_| !list_args.tables) {
                 // list 'tablename' contents
-                let res = api.list_table_contents(
-                    &tablename,
-                    list_args.group.as_deref(),
-                    list_args.sort_by.as_deref(),
-                )?;
+                let mut res =
+                    api.list_table_contents(&tablename, list_args.group.as_deref(), None)?;
 
-                if let Some(formatted_res) = format_list_res(&res) {
-                    println!("{formatted_res}");
+                // filtering and sorting are done client-side on the fetched tasks
+                if let Some(tasks) = res.get_mut("res").and_then(|v| v.as_array_mut()) {
+                    if let Some(filter) = &list_args.filter {
+                        tasks.retain(|task| filter.matches(task));
+                    }
+                    if let Some(sort_by) = &list_args.sort_by {
+                        sort_by.sort(tasks);
+                    }
+                }
+
+                if let Some(formatted_res) = format_list_res(&res, &display) {
+                    display.print(&formatted_res);
                 } else {
                     println!("No data to display.");
                 }
@@ -260,8 +716,8 @@ fn main() -> Result<(), String> {
                 // list table specs
                 let res = api.list_tables_specs()?;
 
-                if let Some(formatted_res) = format_specs_res(&res) {
-                    println!("{formatted_res}");
+                if let Some(formatted_res) = format_specs_res(&res, &display) {
+                    display.print(&formatted_res);
                 } else {
                     println!("No data to display.");
                 }
@@ -271,11 +727,93 @@ fn main() -> Result<(), String> {
         }
         Commands::Add(AddArgs {
             tablename,
+            words,
             task,
             due,
             group,
+            from_file,
+            raw,
+            stop_on_error,
+            dry_run,
         }) => {
-            let res = api.add_task(&tablename, &task, due, group.as_deref())?;
+            let tablename = table(tablename)?;
+            if words.is_empty() && task.is_none() && from_file.is_none() {
+                return Err("No task given, see 'rsm add --help'".to_string());
+            }
+
+            // many tasks, one per line
+            let content = match from_file {
+                Some(path) => Some(
+                    std::fs::read_to_string(&path)
+                        .map_err(|e| format!("Couldn't read the file '{}': {e}", path.display()))?,
+                ),
+                None if words == ["-"] => Some(
+                    std::io::read_to_string(std::io::stdin())
+                        .map_err(|e| format!("Internal error: {e}"))?,
+                ),
+                None => None,
+            };
+            if let Some(content) = content {
+                let mut tasks =
+                    capture::parse_lines(&content, raw, chrono::Local::now().naive_local());
+
+                // the flags are the fields of the tasks giving none
+                for (_, task) in tasks.iter_mut() {
+                    if let Ok(task) = task {
+                        task.due = task.due.take().or(due.clone());
+                        task.group = task.group.take().or(group.clone());
+                    }
+                }
+
+                return add_tasks(api, &tablename, tasks, stop_on_error, dry_run, &display);
+            }
+
+            let parsed = task.is_none() && !raw;
+            let mut capture = match task {
+                Some(task) => capture::Capture {
+                    description: task,
+                    ..Default::default()
+                },
+                None if raw => capture::Capture {
+                    description: words.join(" "),
+                    ..Default::default()
+                },
+                None => {
+                    capture::parse_capture(&words.join(" "), chrono::Local::now().naive_local())?
+                }
+            };
+
+            // the flags can't contradict the inline fields
+            if due.is_some() && capture.due.is_some() {
+                return Err("The due is given both with '--due' and in the task".to_string());
+            }
+            if group.is_some() && capture.group.is_some() {
+                return Err("The group is given both with '--group' and in the task".to_string());
+            }
+            capture.due = capture.due.or(due);
+            capture.group = capture.group.or(group);
+
+            // shows what was understood from the inline fields
+            if parsed || dry_run {
+                println!("{}", format_capture(&capture, &display));
+            }
+            if dry_run {
+                return Ok(());
+            }
+
+            check_table_support(
+                api,
+                &tablename,
+                capture.due.is_some(),
+                capture.group.is_some(),
+            )?;
+
+            let res = api.add_task(
+                &tablename,
+                &capture.description,
+                capture.due,
+                capture.group.as_deref(),
+            )?;
 
             let formatted_res = res
                 .get("res")
@@ -287,13 +825,43 @@ fn main() -> Result<(), String> {
 
             Ok(())
         }
-        Commands::Remove(RemoveArgs { tablename, ids }) => {
+        Commands::Remove(args) => remove_command(api, args, cli.yes, false, &display),
+        Commands::Done(args) => remove_command(api, args, cli.yes, true, &display),
+        Commands::Update(UpdateArgs {
+            tablename,
+            ids,
+            pick,
+            task,
+            due,
+            group,
+        }) => {
+            let tablename = table(tablename)?;
+            check_table_support(api, &tablename, due.is_some(), group.is_some())?;
+
+            let Some(ids) = resolve_ids(api, &tablename, ids, pick)? else {
+                println!("Aborted.");
+                return Ok(());
+            };
+            let tasks = fetch_tasks(api, &tablename)?;
+
+            let results = api::parallel(&ids, api::MAX_PARALLEL_REQUESTS, |id| {
+                api.update_task(
+                    &tablename,
+                    *id,
+                    task.as_deref(),
+                    due.clone(),
+                    group.as_deref(),
+                )
+            });
+
             println!();
-            for id in ids.into_iter().flatten() {
-                // handle the res with a match so if there is an error it continues
-                // to delete other eventual ids
-                match api.remove_task(&tablename, id) {
+            let mut updated = vec![];
+            for (id, res) in ids.into_iter().zip(results) {
+                // same as remove, an error on an id doesn't stop the others
+                match res {
                     Ok(res) => {
+                        updated.push(id);
+
                         let formatted_res = res
                             .get("res")
                             .map(|v| v.as_str().unwrap_or_default())
@@ -302,21 +870,40 @@ fn main() -> Result<(), String> {
                         println!("{formatted_res}");
                     }
                     Err(e) => {
-                        println!("Error when removing task with id {id}: {e}");
+                        println!("Error when updating task with id {id}: {e}");
                     }
                 }
             }
 
+            let previous = tasks
+                .into_iter()
+                .filter(|t| {
+                    t.get("id")
+                        .and_then(|v| v.as_u64())
+                        .is_some_and(|id| updated.contains(&(id as usize)))
+                })
+                .collect();
+            journal::record(journal::Entry::new(Operation::Update, &tablename, previous))?;
+
             Ok(())
         }
-        Commands::Update(UpdateArgs {
-            tablename,
-            id,
-            task,
-            due,
-            group,
-        }) => {
-            let res = api.update_task(&tablename, &id, task.as_deref(), due, group.as_deref())?;
+        Commands::Clear(ClearArgs { tablename }) => {
+            let tablename = table(tablename)?;
+            // the tasks are fetched even with '--yes', 'rsm undo' needs them
+            let tasks = fetch_tasks(api, &tablename)?;
+            if !cli.yes {
+                let question = format!(
+                    "This will remove all the {} task(s) of the table '{tablename}', continue?",
+                    tasks.len()
+                );
+                if !confirm(&question, false)? {
+                    println!("Aborted.");
+                    return Ok(());
+                }
+            }
+
+            let res = api.clear_table(&tablename)?;
+            journal::record(journal::Entry::new(Operation::Clear, &tablename, tasks))?;
 
             let formatted_res = res
                 .get("res")
@@ -328,8 +915,271 @@ fn main() -> Result<(), String> {
 
             Ok(())
         }
-        Commands::Clear(ClearArgs { tablename }) => {
-            let res = api.clear_table(&tablename)?;
+        Commands::Undo(UndoArgs { list, n }) => {
+            if list {
+                if let Some(formatted_res) = format_journal(&journal::load(), &display) {
+                    display.print(&formatted_res);
+                } else {
+                    println!("Nothing to undo.");
+                }
+
+                return Ok(());
+            }
+
+            // the entry is only dropped from the journal once it is restored
+            let entry = journal::get(n.unwrap_or(1))?;
+            let restored = journal::restore(api, &entry);
+            // undoing a drop re-creates the table
+            cache::invalidate();
+            let restored = restored?;
+            journal::settle(&entry, &restored.unrestored)?;
+
+            println!();
+            for line in &restored.report {
+                println!("{line}");
+            }
+            if restored.unrestored.is_empty() {
+                println!(
+                    "Undone '{}' on table '{}' ({} task(s))",
+                    entry.operation,
+                    entry.tablename,
+                    entry.tasks.len()
+                );
+            } else {
+                println!(
+                    "Partly undone '{}' on table '{}', {} of {} task(s) couldn't be restored and stay in the undo journal to retry",
+                    entry.operation,
+                    entry.tablename,
+                    restored.unrestored.len(),
+                    entry.tasks.len()
+                );
+            }
+
+            Ok(())
+        }
+        Commands::Search(SearchArgs {
+            query,
+            table,
+            regex,
+            case_sensitive,
+        }) => {
+            let pattern = if regex { query } else { regex::escape(&query) };
+            let re = regex::RegexBuilder::new(&pattern)
+                .case_insensitive(!case_sensitive)
+                .build()
+                .map_err(|e| format!("Invalid regex: {e}"))?;
+
+            let tablenames = match table {
+                Some(tablename) => vec![tablename],
+                None => cache::fetch_table_specs(api)?
+                    .into_iter()
+                    .map(|spec| spec.name)
+                    .collect(),
+            };
+
+            // a table that can't be read doesn't stop the search of the others
+            let mut hits = vec![];
+            let mut errors = vec![];
+            for tablename in tablenames {
+                let tasks = match fetch_tasks(api, &tablename) {
+                    Ok(tasks) => tasks,
+                    Err(e) => {
+                        errors.push(format!("Error when searching the table '{tablename}': {e}"));
+                        continue;
+                    }
+                };
+
+                for task in tasks {
+                    let matches = ["description", "group"].iter().any(|key| {
+                        task.get(key)
+                            .and_then(|v| v.as_str())
+                            .is_some_and(|v| re.is_match(v))
+                    });
+
+                    if matches {
+                        hits.push((tablename.clone(), task));
+                    }
+                }
+            }
+
+            if let Some(formatted_res) = format_search_res(&hits, &re, &display) {
+                display.print(&formatted_res);
+            } else {
+                println!("No matching task.");
+            }
+            for error in &errors {
+                eprintln!("{error}");
+            }
+
+            Ok(())
+        }
+        Commands::Show(ShowArgs { tablename, id }) => {
+            let tablename = table(tablename)?;
+            let id = id.ok_or_else(|| "No id given, see 'rsm show --help'".to_string())?;
+            let task = fetch_task(api, &tablename, id)?;
+
+            display.print(&format_task_card(&task, &display));
+
+            Ok(())
+        }
+        Commands::Edit(EditArgs {
+            tablename,
+            id: None,
+        }) => {
+            let tablename = table(tablename)?;
+            let spec = fetch_table_spec(api, &tablename)?;
+            let tasks = fetch_tasks(api, &tablename)?;
+
+            let doc = editor::table_document(&tablename, spec.has_due, spec.has_group, &tasks);
+            let Some(plan) = editor::edit_text(&doc, |doc| {
+                editor::parse_table_document(doc, spec.has_due, spec.has_group, &tasks)
+            })?
+            else {
+                println!("Empty file, nothing changed.");
+                return Ok(());
+            };
+
+            if plan.is_empty() {
+                println!("No changes.");
+                return Ok(());
+            }
+
+            println!();
+            for fields in &plan.adds {
+                println!("add:    {}", fields.description);
+            }
+            for (id, changes) in &plan.updates {
+                let mut changed = vec![];
+                if changes.description.is_some() {
+                    changed.push("description");
+                }
+                if changes.due.is_some() {
+                    changed.push("due");
+                }
+                if changes.group.is_some() {
+                    changed.push("group");
+                }
+                println!("update: {id} ({})", changed.join(", "));
+            }
+            for id in &plan.removes {
+                println!("remove: {id}");
+            }
+
+            let question = format!(
+                "Apply {} addition(s), {} update(s) and {} removal(s) to the table '{tablename}'?",
+                plan.adds.len(),
+                plan.updates.len(),
+                plan.removes.len()
+            );
+            if !confirm(&question, cli.yes)? {
+                println!("Aborted.");
+                return Ok(());
+            }
+
+            println!();
+            for fields in plan.adds {
+                let due = fields
+                    .due
+                    .filter(|due| !due.is_empty())
+                    .map(|due| parse_due(&due))
+                    .transpose()?;
+                let group = fields.group.filter(|group| !group.is_empty());
+
+                match api.add_task(&tablename, &fields.description, due, group.as_deref()) {
+                    Ok(_) => println!("Added '{}'", fields.description),
+                    Err(e) => println!("Error when adding '{}': {e}", fields.description),
+                }
+            }
+
+            let mut updated = vec![];
+            for (id, changes) in plan.updates {
+                match api.update_task(
+                    &tablename,
+                    id,
+                    changes.description.as_deref(),
+                    changes.due,
+                    changes.group.as_deref(),
+                ) {
+                    Ok(_) => {
+                        updated.push(id);
+                        println!("Updated task with id {id}");
+                    }
+                    Err(e) => println!("Error when updating task with id {id}: {e}"),
+                }
+            }
+
+            let mut removed = vec![];
+            for id in plan.removes {
+                match api.remove_task(&tablename, id) {
+                    Ok(_) => {
+                        removed.push(id);
+                        println!("Removed task with id {id}");
+                    }
+                    Err(e) => println!("Error when removing task with id {id}: {e}"),
+                }
+            }
+
+            // the previous state of what was touched goes in the undo journal
+            let previous = |ids: &[usize]| -> Vec<serde_json::Value> {
+                tasks
+                    .iter()
+                    .filter(|task| {
+                        task.get("id")
+                            .and_then(|v| v.as_u64())
+                            .is_some_and(|id| ids.contains(&(id as usize)))
+                    })
+                    .cloned()
+                    .collect()
+            };
+            journal::record(journal::Entry::new(
+                Operation::Update,
+                &tablename,
+                previous(&updated),
+            ))?;
+            journal::record(journal::Entry::new(
+                Operation::Remove,
+                &tablename,
+                previous(&removed),
+            ))?;
+
+            Ok(())
+        }
+        Commands::Edit(EditArgs {
+            tablename,
+            id: Some(id),
+        }) => {
+            let tablename = table(tablename)?;
+            let task = fetch_task(api, &tablename, id)?;
+            let original = editor::TaskFields::from_json(&task);
+
+            let doc = editor::task_document(&tablename, id, &original);
+            let Some(changes) = editor::edit_text(&doc, |doc| {
+                editor::parse_task_document(doc, &original)?
+                    .map(|edited| editor::diff_task(&original, &edited))
+                    .transpose()
+            })?
+            else {
+                println!("Empty file, nothing changed.");
+                return Ok(());
+            };
+
+            if changes.is_empty() {
+                println!("No changes.");
+                return Ok(());
+            }
+
+            let res = api.update_task(
+                &tablename,
+                id,
+                changes.description.as_deref(),
+                changes.due,
+                changes.group.as_deref(),
+            )?;
+            journal::record(journal::Entry::new(
+                Operation::Update,
+                &tablename,
+                vec![task],
+            ))?;
 
             let formatted_res = res
                 .get("res")
@@ -341,6 +1191,747 @@ fn main() -> Result<(), String> {
 
             Ok(())
         }
+        Commands::Shell => shell::run(api),
+        Commands::Tui => tui::run(api),
+        Commands::Move(args) => transfer_tasks(api, args, true),
+        Commands::Copy(args) => transfer_tasks(api, args, false),
+        Commands::Table(TableCommands::Rename(RenameArgs { old, new })) => {
+            let spec = fetch_table_spec(api, &old)?;
+            let tasks = fetch_tasks(api, &old)?;
+
+            let question = format!(
+                "This will re-create the table '{old}' as '{new}', its {} task(s) will get new ids, continue?",
+                tasks.len()
+            );
+            if !confirm(&question, cli.yes)? {
+                println!("Aborted.");
+                return Ok(());
+            }
+
+            println!();
+            migrate_table(
+                api,
+                &spec,
+                &TableSpec {
+                    name: new.clone(),
+                    ..spec.clone()
+                },
+                &tasks,
+            )?;
+            // the default table and the aliases follow the table
+            let mut config = Config::load()?;
+            if config.default_table.as_deref() == Some(old.as_str()) {
+                config.default_table = Some(new.clone());
+            }
+            for target in config.aliases.values_mut().filter(|target| **target == old) {
+                *target = new.clone();
+            }
+            config.save()?;
+
+            println!("Table '{old}' renamed to '{new}', its tasks got new ids");
+
+            Ok(())
+        }
+        Commands::Table(TableCommands::Alter(AlterArgs {
+            tablename,
+            enable_due,
+            disable_due,
+            enable_group,
+            disable_group,
+        })) => {
+            let spec = fetch_table_spec(api, &tablename)?;
+            let altered = TableSpec {
+                name: tablename.clone(),
+                has_due: (spec.has_due || enable_due) && !disable_due,
+                has_group: (spec.has_group || enable_group) && !disable_group,
+            };
+
+            if altered == spec {
+                println!("Nothing to change.");
+                return Ok(());
+            }
+
+            let tasks = fetch_tasks(api, &tablename)?;
+
+            // disabling a feature loses the values
+            let question = if (spec.has_due && !altered.has_due)
+                || (spec.has_group && !altered.has_group)
+            {
+                format!(
+                    "The dues or groups of the table '{tablename}' will be lost and its {} task(s) will get new ids, continue?",
+                    tasks.len()
+                )
+            } else {
+                format!(
+                    "This will re-create the table '{tablename}', its {} task(s) will get new ids, continue?",
+                    tasks.len()
+                )
+            };
+            if !confirm(&question, cli.yes)? {
+                println!("Aborted.");
+                return Ok(());
+            }
+
+            // the backend can't change a table in place, so it is re-created
+            println!();
+            migrate_table(api, &spec, &altered, &tasks)?;
+            println!(
+                "Table '{tablename}' altered (due support: {}, group support: {})",
+                altered.has_due, altered.has_group
+            );
+
+            Ok(())
+        }
+        Commands::Table(TableCommands::Default(DefaultArgs { tablename, unset })) => {
+            let mut config = Config::load()?;
+
+            match (tablename, unset) {
+                (Some(tablename), _) => {
+                    // make sure the table exists, so later commands don't fail on a typo
+                    let spec = cache::table_spec(api, &tablename)?;
+                    config.default_table = Some(spec.name);
+                    config.save()?;
+
+                    println!("Default table set to '{tablename}'");
+                }
+                (None, true) => {
+                    config.default_table = None;
+                    config.save()?;
+
+                    println!("Default table unset");
+                }
+                (None, false) => match config.default_table {
+                    Some(tablename) => println!("The default table is '{tablename}'"),
+                    None => println!("No default table set"),
+                },
+            }
+
+            Ok(())
+        }
+        Commands::Table(TableCommands::Alias(AliasArgs {
+            alias,
+            tablename,
+            remove,
+        })) => {
+            let mut config = Config::load()?;
+
+            match (alias, tablename) {
+                (Some(alias), _) if remove => {
+                    if config.aliases.remove(&alias).is_none() {
+                        return Err(format!("No alias named '{alias}'"));
+                    }
+                    config.save()?;
+
+                    println!("Alias '{alias}' removed");
+                }
+                (Some(alias), Some(tablename)) => {
+                    // a table would be shadowed by an alias of the same name
+                    let specs = cache::fetch_table_specs(api)?;
+                    if specs.iter().any(|spec| spec.name == alias) {
+                        return Err(format!("'{alias}' is already the name of a table"));
+                    }
+                    if !specs.iter().any(|spec| spec.name == tablename) {
+                        return Err(format!("Table '{tablename}' not found"));
+                    }
+
+                    config.aliases.insert(alias.clone(), tablename.clone());
+                    config.save()?;
+
+                    println!("'{alias}' is now an alias of '{tablename}'");
+                }
+                (Some(alias), None) => match config.aliases.get(&alias) {
+                    Some(tablename) => println!("{alias} -> {tablename}"),
+                    None => return Err(format!("No alias named '{alias}'")),
+                },
+                (None, _) => {
+                    if config.aliases.is_empty() {
+                        println!("No alias set");
+                    }
+                    for (alias, tablename) in &config.aliases {
+                        println!("{alias} -> {tablename}");
+                    }
+                }
+            }
+
+            Ok(())
+        }
         _ => unreachable!(), // This handles exhaustive checking without runtime cost
     }
 }
+
+/// Fetches every task of a table, used to show what a destructive command will affect.
+fn fetch_tasks(api: &Api, tablename: &str) -> Result<Vec<serde_json::Value>, String> {
+    let res = api.list_table_contents(tablename, None, None)?;
+
+    Ok(res
+        .get("res")
+        .and_then(|v| v.as_array())
+        .cloned()
+        .unwrap_or_default())
+}
+
+/// Resolves the ids given on the command line, or lets the user pick them when `pick` is set.
+///
+/// `None` means the user cancelled the picker.
+fn resolve_ids(
+    api: &Api,
+    tablename: &str,
+    ids: Vec<IdSelection>,
+    pick: bool,
+) -> Result<Option<Vec<usize>>, String> {
+    if !pick {
+        if ids.is_empty() {
+            return Err("No ids given, pass them or '--pick'".to_string());
+        }
+
+        // plain ids don't need the tasks
+        let tasks = if ids.iter().any(IdSelection::needs_tasks) {
+            fetch_tasks(api, tablename)?
+        } else {
+            vec![]
+        };
+
+        return utils::select_ids(&ids, &tasks, chrono::Local::now().naive_local()).map(Some);
+    }
+
+    let tasks = fetch_tasks(api, tablename)?;
+    let picked = picker::pick_tasks(&tasks, &format!("tasks of '{tablename}'"))?;
+
+    Ok((!picked.is_empty()).then_some(picked))
+}
+
+/// Removes the tasks given by id, picked or matching the filters of 'remove' and 'done'.
+fn remove_command(
+    api: &Api,
+    args: RemoveArgs,
+    assume_yes: bool,
+    done: bool,
+    display: &Display,
+) -> Result<(), String> {
+    let RemoveArgs {
+        tablename,
+        ids,
+        pick,
+        group,
+        due_before,
+        matches,
+    } = args;
+    let tablename = table(tablename)?;
+
+    // ids read in place of the table aren't checked against the filters by clap
+    if !ids.is_empty() && (group.is_some() || due_before.is_some() || matches.is_some()) {
+        return Err(
+            "The ids can't be combined with '--group', '--due-before' or '--match'".to_string(),
+        );
+    }
+
+    if group.is_none() && due_before.is_none() && matches.is_none() {
+        let Some(ids) = resolve_ids(api, &tablename, ids, pick)? else {
+            println!("Aborted.");
+            return Ok(());
+        };
+        return remove_tasks(api, &tablename, ids, false, assume_yes, done, display);
+    }
+
+    check_table_support(api, &tablename, due_before.is_some(), group.is_some())?;
+
+    // the group is filtered by the api, the rest client-side like 'list --where'
+    let res = api.list_table_contents(&tablename, group.as_deref(), None)?;
+    let ids: Vec<usize> = res
+        .get("res")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|task| {
+            [&due_before, &matches]
+                .into_iter()
+                .flatten()
+                .all(|f| f.matches(task))
+        })
+        .filter_map(|task| task.get("id").and_then(|v| v.as_u64()))
+        .map(|id| id as usize)
+        .collect();
+
+    if ids.is_empty() {
+        println!();
+        println!("No task matches the filters");
+        return Ok(());
+    }
+
+    remove_tasks(api, &tablename, ids, true, assume_yes, done, display)
+}
+
+/// Removes tasks, asking for confirmation when there are several of them or they were filtered.
+///
+/// The backend has no notion of a done task, so `done` removes them as well,
+/// only the messages differ.
+fn remove_tasks(
+    api: &Api,
+    tablename: &str,
+    ids: Vec<usize>,
+    filtered: bool,
+    assume_yes: bool,
+    done: bool,
+    display: &Display,
+) -> Result<(), String> {
+    let tasks: Vec<serde_json::Value> = fetch_tasks(api, tablename)?
+        .into_iter()
+        .filter(|task| {
+            task.get("id")
+                .and_then(|v| v.as_u64())
+                .is_some_and(|id| ids.contains(&(id as usize)))
+        })
+        .collect();
+
+    // the ids without a task are reported once, rather than failing one request each
+    let asked = ids.len();
+    let (ids, missing): (Vec<usize>, Vec<usize>) = ids.into_iter().partition(|id| {
+        tasks
+            .iter()
+            .any(|task| task.get("id").and_then(|v| v.as_u64()) == Some(*id as u64))
+    });
+    if !missing.is_empty() {
+        let missing: Vec<String> = missing.iter().map(usize::to_string).collect();
+        let message = format!(
+            "No task with id {} in the table '{tablename}'",
+            missing.join(", ")
+        );
+        if ids.is_empty() {
+            return Err(message);
+        }
+        println!("{message}");
+    }
+
+    // a single id is explicit enough, only ask when removing many tasks at once
+    // or the ones matching a filter
+    if asked > 1 || filtered {
+        if let Some(formatted_res) = format_list_res(&serde_json::json!({ "res": tasks }), display)
+        {
+            println!("{formatted_res}");
+        }
+
+        let question = if done {
+            format!(
+                "This will mark {} task(s) of the table '{tablename}' as done, continue?",
+                tasks.len()
+            )
+        } else {
+            format!(
+                "This will remove {} task(s) from the table '{tablename}', continue?",
+                tasks.len()
+            )
+        };
+        if !confirm(&question, assume_yes)? {
+            println!("Aborted.");
+            return Ok(());
+        }
+    }
+
+    let results = api::parallel(&ids, api::MAX_PARALLEL_REQUESTS, |id| {
+        api.remove_task(tablename, *id)
+    });
+
+    println!();
+    let mut removed = vec![];
+    for (id, res) in ids.into_iter().zip(results) {
+        // an error on an id doesn't stop the others
+        match res {
+            Ok(res) => {
+                removed.push(id);
+
+                let formatted_res = res
+                    .get("res")
+                    .map(|v| v.as_str().unwrap_or_default())
+                    .unwrap_or_default();
+
+                if done {
+                    println!("Task with id {id} done");
+                } else {
+                    println!("{formatted_res}");
+                }
+            }
+            Err(e) => {
+                println!("Error when removing task with id {id}: {e}");
+            }
+        }
+    }
+
+    // only what was actually removed can be restored
+    let tasks = tasks
+        .into_iter()
+        .filter(|task| {
+            task.get("id")
+                .and_then(|v| v.as_u64())
+                .is_some_and(|id| removed.contains(&(id as usize)))
+        })
+        .collect();
+    journal::record(journal::Entry::new(Operation::Remove, tablename, tasks))
+}
+
+/// Adds the tasks to the destination table, and removes them from the source one
+/// if `remove_source` is set, only once the add succeeded.
+fn transfer_tasks(api: &Api, args: TransferArgs, remove_source: bool) -> Result<(), String> {
+    let TransferArgs {
+        from,
+        ids,
+        to,
+        group,
+    } = args;
+    let destination = fetch_table_spec(api, &to)?;
+    let tasks = fetch_tasks(api, &from)?;
+    let ids = utils::select_ids(&ids, &tasks, chrono::Local::now().naive_local())?;
+
+    // dues and groups the destination can't hold are dropped, with a warning
+    let has = |key: &str| {
+        tasks.iter().any(|task| {
+            task.get("id")
+                .and_then(|v| v.as_u64())
+                .is_some_and(|id| ids.contains(&(id as usize)))
+                && task.get(key).is_some_and(|v| !v.is_null())
+        })
+    };
+    println!();
+    if !destination.has_due && has("due") {
+        println!("Warning: '{to}' has no due support, the dues are dropped");
+    }
+    if !destination.has_group && (has("group") || group.is_some()) {
+        println!("Warning: '{to}' has no group support, the groups are dropped");
+    }
+
+    // the ids the moved tasks get in the destination are needed to undo the move
+    let existing = if remove_source {
+        journal::task_ids(api, &to)?
+    } else {
+        Default::default()
+    };
+
+    let mut moved = vec![];
+    // whether each task added to the destination was moved, in the order of the adds
+    let mut added = vec![];
+    for id in ids {
+        let Some(task) = tasks
+            .iter()
+            .find(|task| task.get("id").and_then(|v| v.as_u64()) == Some(id as u64))
+        else {
+            println!("Error when transferring task with id {id}: TaskNotFound");
+            continue;
+        };
+
+        let due = task
+            .get("due")
+            .and_then(Due::from_api)
+            .filter(|_| destination.has_due);
+        let task_group = group
+            .as_deref()
+            .or_else(|| task.get("group").and_then(|v| v.as_str()))
+            .filter(|_| destination.has_group);
+
+        let description = task
+            .get("description")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+
+        if let Err(e) = api.add_task(&to, description, due, task_group) {
+            println!("Error when adding task with id {id} to '{to}': {e}");
+            continue;
+        }
+
+        if !remove_source {
+            println!("Copied task with id {id} to '{to}'");
+            continue;
+        }
+
+        match api.remove_task(&from, id) {
+            Ok(_) => {
+                moved.push(task.clone());
+                added.push(true);
+                println!("Moved task with id {id} to '{to}'");
+            }
+            Err(e) => {
+                added.push(false);
+                println!(
+                    "Error when removing task with id {id} from '{from}', it was copied instead: {e}"
+                )
+            }
+        }
+    }
+
+    if moved.is_empty() {
+        return Ok(());
+    }
+
+    let destination_ids = added
+        .into_iter()
+        .zip(journal::new_ids(api, &to, &existing)?)
+        .filter_map(|(moved, id)| moved.then_some(id))
+        .collect();
+
+    journal::record(journal::Entry {
+        destination: Some(to),
+        destination_ids,
+        ..journal::Entry::new(Operation::Move, &from, moved)
+    })
+}
+
+/// Re-creates the table `from` as `to` with the given tasks, dropping `from`.
+///
+/// The tasks get new ids from the backend, and the drop of `from` is journaled.
+/// A renamed table is dropped last, so on failure the new table is dropped and `from`
+/// is left as it was. A table altered in place has to be dropped first, on failure
+/// it stays dropped and 'rsm undo' brings it back.
+fn migrate_table(
+    api: &Api,
+    from: &TableSpec,
+    to: &TableSpec,
+    tasks: &[serde_json::Value],
+) -> Result<(), String> {
+    let in_place = from.name == to.name;
+
+    // journaled first, an altered table must not be lost if the journal can't be written
+    let drop_from = || -> Result<(), String> {
+        let entry = journal::Entry {
+            has_due: from.has_due,
+            has_group: from.has_group,
+            ..journal::Entry::new(Operation::Drop, &from.name, tasks.to_vec())
+        };
+        journal::record(entry.clone())?;
+        if let Err(e) = api.drop_table(&from.name) {
+            let _ = journal::discard(&entry);
+            return Err(e);
+        }
+        cache::invalidate();
+        Ok(())
+    };
+
+    let unchanged = |e: String| {
+        format!(
+            "Couldn't migrate the table '{}', nothing was changed: {e}",
+            from.name
+        )
+    };
+    let dropped = |e: String| {
+        format!(
+            "Couldn't alter the table '{}': {e}, it was dropped, 'rsm undo' restores it",
+            from.name
+        )
+    };
+
+    if in_place {
+        drop_from().map_err(unchanged)?;
+    }
+
+    api.create_table(&to.name, to.has_due, to.has_group)
+        .map_err(|e| if in_place { dropped(e) } else { unchanged(e) })?;
+    cache::invalidate();
+
+    let rollback = |e: String| {
+        match (api.drop_table(&to.name), in_place) {
+        (Ok(_), false) => unchanged(e),
+        (Ok(_), true) => dropped(e),
+        (Err(_), false) => format!(
+            "{}, but the partial copy '{}' couldn't be dropped",
+            unchanged(e),
+            to.name
+        ),
+        (Err(_), true) => format!(
+            "Couldn't alter the table '{}': {e}, it was dropped and re-created with part of its tasks, drop it again then 'rsm undo 2' restores it",
+            from.name
+        ),
+    }
+    };
+
+    for task in tasks {
+        api.add_task(
+            &to.name,
+            task.get("description")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default(),
+            task.get("due")
+                .and_then(Due::from_api)
+                .filter(|_| to.has_due),
+            task.get("group")
+                .and_then(|v| v.as_str())
+                .filter(|_| to.has_group),
+        )
+        .map_err(rollback)?;
+    }
+
+    if !in_place {
+        drop_from().map_err(|e| {
+            format!(
+                "The tasks of '{}' were copied to '{}', but it couldn't be dropped: {e}",
+                from.name, to.name
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Fetches a single task, there's no endpoint for it so it's looked up in the table contents.
+fn fetch_task(api: &Api, tablename: &str, id: usize) -> Result<serde_json::Value, String> {
+    fetch_tasks(api, tablename)?
+        .into_iter()
+        .find(|task| task.get("id").and_then(|v| v.as_u64()) == Some(id as u64))
+        .ok_or_else(|| format!("Task with id {id} not found in table '{tablename}'"))
+}
+
+/// Looks up the specs of a single table, always fetched fresh from the api.
+fn fetch_table_spec(api: &Api, tablename: &str) -> Result<TableSpec, String> {
+    cache::fetch_table_specs(api)?
+        .into_iter()
+        .find(|spec| spec.name == tablename)
+        .ok_or_else(|| format!("Table '{tablename}' not found"))
+}
+
+/// Adds the parsed lines of a batch, reporting the invalid and failed ones by line number.
+///
+/// The tasks are sent in parallel, so their ids can be in a different order than the lines.
+/// With `stop_on_error`, an invalid line stops the batch before anything is added,
+/// and the tasks are sent one by one so a failed one stops it where it failed.
+fn add_tasks(
+    api: &Api,
+    tablename: &str,
+    tasks: Vec<(usize, Result<capture::Capture, String>)>,
+    stop_on_error: bool,
+    dry_run: bool,
+    display: &Display,
+) -> Result<(), String> {
+    let spec = cache::table_spec(api, tablename)?;
+
+    // unsupported fields are caught before anything is added, like invalid lines
+    let tasks: Vec<(usize, Result<capture::Capture, String>)> = tasks
+        .into_iter()
+        .map(|(line, task)| {
+            let task = task.and_then(|task| {
+                check_spec_support(&spec, task.due.is_some(), task.group.is_some()).map(|_| task)
+            });
+            (line, task)
+        })
+        .collect();
+
+    let mut failed = 0;
+    for (line, task) in &tasks {
+        if let Err(e) = task {
+            eprintln!("Line {line}: {e}");
+            failed += 1;
+        }
+    }
+    if failed > 0 && stop_on_error {
+        return Err(format!("{failed} invalid line(s), no task added"));
+    }
+
+    let unsaved = tasks
+        .iter()
+        .filter(|(_, task)| {
+            task.as_ref()
+                .is_ok_and(|task| !task.tags.is_empty() || task.priority.is_some())
+        })
+        .count();
+    if unsaved > 0 {
+        eprintln!("Warning: the api has no field for tags and priorities, those of {unsaved} line(s) aren't saved");
+    }
+
+    let valid: Vec<(usize, capture::Capture)> = tasks
+        .into_iter()
+        .filter_map(|(line, task)| Some((line, task.ok()?)))
+        .collect();
+    if valid.is_empty() {
+        return Err("No task to add".to_string());
+    }
+
+    if dry_run {
+        for (line, task) in &valid {
+            println!("Line {line}:");
+            println!("{}", format_capture(task, display));
+        }
+        println!();
+        println!(
+            "{} task(s) would be added to '{}', {failed} invalid line(s)",
+            valid.len(),
+            spec.name
+        );
+        return Ok(());
+    }
+
+    // the progress is only drawn for a person watching
+    let progress = std::io::stderr().is_terminal();
+    let total = valid.len();
+    let sent = AtomicUsize::new(0);
+    let stopped = AtomicBool::new(false);
+
+    // stopping at the first failure means adding them one by one, nothing is sent past it
+    let workers = if stop_on_error {
+        1
+    } else {
+        api::MAX_PARALLEL_REQUESTS
+    };
+    let results = api::parallel(&valid, workers, |(_, task)| {
+        if stopped.load(Ordering::Relaxed) {
+            return None;
+        }
+        if progress {
+            eprint!(
+                "\rAdding tasks {}/{total}",
+                sent.fetch_add(1, Ordering::Relaxed) + 1
+            );
+        }
+
+        let res = api.add_task(
+            tablename,
+            &task.description,
+            task.due.clone(),
+            task.group.as_deref(),
+        );
+        if res.is_err() && stop_on_error {
+            stopped.store(true, Ordering::Relaxed);
+        }
+        Some(res)
+    });
+    if progress {
+        eprintln!();
+    }
+
+    let mut added = 0;
+    for ((line, _), res) in valid.iter().zip(results) {
+        match res {
+            Some(Ok(_)) => added += 1,
+            Some(Err(e)) => {
+                eprintln!("Line {line}: {e}");
+                failed += 1;
+            }
+            None => {}
+        }
+    }
+
+    println!();
+    println!("{added} task(s) added to '{}', {failed} failed", spec.name);
+
+    if failed > 0 && stop_on_error {
+        return Err("Stopped at the first failed task".to_string());
+    }
+
+    Ok(())
+}
+
+/// Rejects a due or a group on a table that doesn't support it, before calling the api.
+fn check_table_support(api: &Api, tablename: &str, due: bool, group: bool) -> Result<(), String> {
+    check_spec_support(&cache::table_spec(api, tablename)?, due, group)
+}
+
+fn check_spec_support(spec: &TableSpec, due: bool, group: bool) -> Result<(), String> {
+    let unsupported = match (due && !spec.has_due, group && !spec.has_group) {
+        (true, true) => "dues or groups",
+        (true, false) => "dues",
+        (false, true) => "groups",
+        (false, false) => return Ok(()),
+    };
+
+    let yes_no = |b: bool| if b { "Yes" } else { "No" };
+    Err(format!(
+        "The table '{}' doesn't support {unsupported} (due support: {}, group support: {})",
+        spec.name,
+        yes_no(spec.has_due),
+        yes_no(spec.has_group)
+    ))
+}
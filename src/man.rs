@@ -0,0 +1,160 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use clap_mangen::Man;
+use roff::{bold, roman, Roff};
+
+const DUE_FORMAT: &[&str] = &[
+    "A due is either 'hh:mm' or 'YYYY-MM-dd hh:mm'.",
+    "A bare 'hh:mm' is today at that time, or tomorrow if that time is already past.",
+];
+
+const ID_SELECTION: &[&str] = &[
+    "Ids are given as one or more values separated by spaces or commas, each one being \
+     a single id (42), an inclusive range (10..15) or an open one (10.., ..5).",
+    "Selectors are resolved against the tasks of the table: all, last (the highest id), \
+     overdue (due before now) and group:<name>.",
+    "A value prefixed with '^' is excluded, e.g. '1..20 ^7 ^9' or 'all ^group:home'. \
+     Values can be combined, duplicates are ignored.",
+];
+
+const FILTER_EXPRESSIONS: &[&str] = &[
+    "A filter compares a field (id, description, due, group) to a value with one of \
+     =, !=, <, <=, >, >=, ~ (case insensitive regex match) or !~.",
+    "Comparisons are combined with and, or, not and parentheses, \
+     e.g. 'due < tomorrow and group != home and description ~ \"deploy\"'.",
+    "Dues accept now, today, tomorrow, yesterday, 'YYYY-MM-dd' or 'YYYY-MM-dd hh:mm', \
+     a day without a time covers the whole day. 'none' matches a missing due or group.",
+];
+
+const QUICK_CAPTURE: &[&str] = &[
+    "The words of 'rsm add' can hold the fields of the task: '#group' (starting with a letter, \
+     '#123' stays in the description), 'due:<date>' and a date at the end, \
+     e.g. 'rsm add work call bob tomorrow 10:00 #calls'.",
+    "Dates are today, tomorrow, a weekday, 'next <weekday>', 'YYYY-MM-dd' or 'in 2 days', \
+     optionally with a time ('10:00', '3pm', 'noon'). A day without a time is at 9:00. \
+     At the end of the text a short weekday ('sun', 'wed') alone stays in the description, \
+     write it in full or with a time, 'on' or 'next'.",
+    "'@tag' and '!priority' are taken out of the description and shown in the preview, \
+     the api has no field to save them. \
+     '--raw' or '-t' take the task as is.",
+    "'rsm add <table> -' and '--from-file <file>' add a task per line, either such a text \
+     or a JSON object like '{\"description\": \"call bob\", \"due\": \"2026-10-20 10:00\"}'.",
+];
+
+const DEFAULT_TABLE: &[&str] = &[
+    "list, add, remove, done, update, clear, show and edit use the default table when it's \
+     left out, it's set with 'rsm table default <table>'.",
+    "Tables aren't looked up while reading the command line, so with a default table a first \
+     argument that reads as ids (a single id for show and edit) is taken as the ids, and a single \
+     argument to add as the task: 'rsm remove 3..5', 'rsm add \"call bob tomorrow\"'.",
+    "Aliases set with 'rsm table alias <alias> <table>' can be used wherever a table is expected.",
+    "Both are kept in the '.config.toml' file next to the '.token' file.",
+];
+
+const DISPLAY: &[&str] = &[
+    "The '[display]' section of the '.config.toml' file sets how the tasks are shown.",
+    "'style' is rounded (the default), ascii, markdown, psql or blank. 'header_color' and \
+     'id_color' are colour names (black, red, green, yellow, blue, magenta, cyan, white) \
+     optionally with bold, e.g. \"bold green\", or none.",
+    "'columns' lists the columns of the task tables in order, among id, description, due \
+     and group, e.g. '[\"due\", \"description\"]'. 'date_format' is a strftime format, \
+     '%Y-%m-%d %H:%M' by default.",
+    "'dues' is absolute (2026-10-20 10:00), relative (in 3h, yesterday, overdue 2d) or both. \
+     With 'due_colors', overdue rows are in 'overdue_color' (red by default), rows due today \
+     in 'today_color' (yellow) and rows due within 'soon_hours' (48) in 'soon_color' (cyan).",
+    "'rsm show' lists every field of the task, those of 'columns' first, with the due both \
+     absolute and relative whatever 'dues' is.",
+    "A bad value in '[display]' is reported and the defaults are used instead.",
+    "'--no-color' or a non-empty NO_COLOR environment variable turn every colour off, \
+     as does printing to a pipe or a file.",
+    "Tables are shrunk to the width of the terminal, wrapping the descriptions, \
+     and drawn in ASCII when TERM is 'dumb'. Tables taller than the terminal go through \
+     $PAGER ('less' by default), '--no-pager' or PAGER=cat print them directly.",
+];
+
+/// Appends a section made of one paragraph per line.
+fn section(roff: &mut Roff, title: &str, paragraphs: &[&str]) {
+    roff.control("SH", [title]);
+    for (i, paragraph) in paragraphs.iter().enumerate() {
+        if i > 0 {
+            roff.control("PP", []);
+        }
+        roff.text([roman(*paragraph)]);
+    }
+}
+
+/// Renders the page of `cmd`, with the grammar sections of the arguments it takes.
+///
+/// The top level page gets every grammar section.
+fn render(cmd: clap::Command, top_level: bool, w: &mut dyn Write) -> io::Result<()> {
+    let takes = |id: &str| top_level || cmd.get_arguments().any(|arg| arg.get_id() == id);
+    let (due, ids, filter, words) = (takes("due"), takes("ids"), takes("filter"), takes("words"));
+
+    Man::new(cmd).render(w)?;
+
+    let mut roff = Roff::new();
+    if due {
+        section(&mut roff, "DUE FORMAT", DUE_FORMAT);
+    }
+    if ids {
+        section(&mut roff, "ID SELECTION", ID_SELECTION);
+    }
+    if filter {
+        section(&mut roff, "FILTER EXPRESSIONS", FILTER_EXPRESSIONS);
+    }
+    if words {
+        section(&mut roff, "QUICK CAPTURE", QUICK_CAPTURE);
+    }
+    if top_level {
+        section(&mut roff, "DEFAULT TABLE AND ALIASES", DEFAULT_TABLE);
+        section(&mut roff, "DISPLAY", DISPLAY);
+
+        roff.control("SH", ["SEE ALSO"]);
+        roff.text([bold("rsm-<command>"), roman("(1) for each command.")]);
+    }
+
+    roff.to_writer(w)
+}
+
+/// Prints the top level man page.
+pub fn print(cmd: clap::Command) -> Result<(), String> {
+    let mut cmd = cmd.disable_help_subcommand(true);
+    cmd.build();
+
+    render(cmd, true, &mut io::stdout()).map_err(|e| format!("Internal error: {e}"))
+}
+
+/// Writes the man page of `cmd` and of every subcommand into `out_dir`, returning the files written.
+pub fn generate_to(cmd: clap::Command, out_dir: &Path) -> Result<Vec<String>, String> {
+    fn generate(
+        cmd: clap::Command,
+        top_level: bool,
+        out_dir: &Path,
+        written: &mut Vec<String>,
+    ) -> io::Result<()> {
+        for sub in cmd.get_subcommands().filter(|s| !s.is_hide_set()).cloned() {
+            generate(sub, false, out_dir, written)?;
+        }
+
+        let path = out_dir.join(Man::new(cmd.clone()).get_filename());
+        let mut file = fs::File::create(&path)?;
+        render(cmd, top_level, &mut file)?;
+
+        written.push(path.to_string_lossy().into_owned());
+        Ok(())
+    }
+
+    fs::create_dir_all(out_dir).map_err(|e| format!("Internal error: {e}"))?;
+
+    let mut cmd = cmd.disable_help_subcommand(true);
+    cmd.build();
+
+    let mut written = vec![];
+    generate(cmd, true, out_dir, &mut written).map_err(|e| format!("Internal error: {e}"))?;
+
+    Ok(written)
+}
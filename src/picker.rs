@@ -0,0 +1,290 @@
+use std::io::{self, IsTerminal};
+
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState, Paragraph},
+    DefaultTerminal, Frame, TerminalOptions, Viewport,
+};
+use serde_json::Value;
+
+use crate::utils::Due;
+
+// lines taken below the prompt, the list scrolls past it
+const MAX_HEIGHT: u16 = 15;
+
+struct Item {
+    id: usize,
+    line: String,
+}
+
+impl Item {
+    fn from_json(task: &Value) -> Self {
+        let id = task.get("id").and_then(|v| v.as_u64()).unwrap_or_default() as usize;
+
+        let mut fields = vec![format!("{id:>4}")];
+        if let Some(due) = task.get("due") {
+            fields.push(
+                Due::from_api(due)
+                    .map(|due| due.0.format("%Y-%m-%d %H:%M").to_string())
+                    .unwrap_or_else(|| format!("{:16}", "N/A")),
+            );
+        }
+        if let Some(group) = task.get("group") {
+            fields.push(format!("{:12}", group.as_str().unwrap_or("N/A")));
+        }
+        fields.push(
+            task.get("description")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+        );
+
+        Item {
+            id,
+            line: fields.join("  "),
+        }
+    }
+}
+
+struct Picker {
+    title: String,
+    items: Vec<Item>,
+    query: String,
+    // indices of the matching items, best first, with the positions of the matched chars
+    matches: Vec<(usize, Vec<usize>)>,
+    cursor: ListState,
+    selected: Vec<usize>,
+    matcher: SkimMatcherV2,
+}
+
+/// Lets the user pick tasks with a fuzzy finder drawn below the prompt.
+///
+/// Returns the picked ids in the order of `tasks`, or nothing if the user cancelled.
+pub fn pick_tasks(tasks: &[Value], title: &str) -> Result<Vec<usize>, String> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err("Can't pick tasks, the terminal is not interactive".to_string());
+    }
+    if tasks.is_empty() {
+        return Err("The table has no task to pick".to_string());
+    }
+
+    let mut picker = Picker {
+        title: title.to_string(),
+        items: tasks.iter().map(Item::from_json).collect(),
+        query: String::new(),
+        matches: vec![],
+        cursor: ListState::default(),
+        selected: vec![],
+        matcher: SkimMatcherV2::default(),
+    };
+    picker.refresh();
+
+    let height = (picker.items.len() as u16 + 2).min(MAX_HEIGHT);
+    let mut terminal = ratatui::try_init_with_options(TerminalOptions {
+        viewport: Viewport::Inline(height),
+    })
+    .map_err(|e| {
+        ratatui::restore();
+        format!("Internal error: couldn't open the picker: {e}")
+    })?;
+    let res = picker.run(&mut terminal);
+    // leave the prompt as it was
+    let _ = terminal.clear();
+    ratatui::restore();
+
+    res
+}
+
+impl Picker {
+    fn refresh(&mut self) {
+        self.matches = if self.query.is_empty() {
+            (0..self.items.len()).map(|i| (i, vec![])).collect()
+        } else {
+            let mut scored: Vec<(i64, usize, Vec<usize>)> = self
+                .items
+                .iter()
+                .enumerate()
+                .filter_map(|(i, item)| {
+                    let (score, indices) = self.matcher.fuzzy_indices(&item.line, &self.query)?;
+                    Some((score, i, indices))
+                })
+                .collect();
+            // stable, so equal scores keep the table order
+            scored.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+
+            scored
+                .into_iter()
+                .map(|(_, i, indices)| (i, indices))
+                .collect()
+        };
+
+        self.cursor.select((!self.matches.is_empty()).then_some(0));
+    }
+
+    fn current(&self) -> Option<usize> {
+        let (i, _) = self.matches.get(self.cursor.selected()?)?;
+        Some(self.items[*i].id)
+    }
+
+    fn toggle(&mut self, id: usize) {
+        match self.selected.iter().position(|selected| *selected == id) {
+            Some(i) => {
+                self.selected.remove(i);
+            }
+            None => self.selected.push(id),
+        }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<Vec<usize>, String> {
+        loop {
+            terminal
+                .draw(|frame| self.draw(frame))
+                .map_err(|e| format!("Internal error: {e}"))?;
+
+            let Event::Key(key) = event::read().map_err(|e| format!("Internal error: {e}"))? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+            match key.code {
+                KeyCode::Esc => return Ok(vec![]),
+                KeyCode::Char('c') if ctrl => return Ok(vec![]),
+                KeyCode::Enter => {
+                    // without an explicit selection, the task under the cursor is picked
+                    if self.selected.is_empty() {
+                        self.selected.extend(self.current());
+                    }
+
+                    return Ok(self
+                        .items
+                        .iter()
+                        .map(|item| item.id)
+                        .filter(|id| self.selected.contains(id))
+                        .collect());
+                }
+                KeyCode::Tab => {
+                    if let Some(id) = self.current() {
+                        self.toggle(id);
+                        self.cursor.select_next();
+                    }
+                }
+                KeyCode::Char('a') if ctrl => {
+                    let ids: Vec<usize> = self
+                        .matches
+                        .iter()
+                        .map(|(i, _)| self.items[*i].id)
+                        .collect();
+
+                    // selects every match, or unselects them when they all are
+                    let all = ids.iter().all(|id| self.selected.contains(id));
+                    self.selected.retain(|id| !ids.contains(id));
+                    if !all {
+                        self.selected.extend(ids);
+                    }
+                }
+                KeyCode::Up => self.cursor.select_previous(),
+                KeyCode::Char('p') if ctrl => self.cursor.select_previous(),
+                KeyCode::Down => self.cursor.select_next(),
+                KeyCode::Char('n') if ctrl => self.cursor.select_next(),
+                KeyCode::Backspace => {
+                    self.query.pop();
+                    self.refresh();
+                }
+                KeyCode::Char(c) if !ctrl => {
+                    self.query.push(c);
+                    self.refresh();
+                }
+                _ => {}
+            }
+
+            // select_next can go past the last match
+            if let Some(i) = self.cursor.selected() {
+                self.cursor
+                    .select(Some(i.min(self.matches.len().saturating_sub(1))));
+            }
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [prompt, list, footer] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let dim = Style::default().fg(Color::DarkGray);
+
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::styled(
+                    "> ",
+                    Style::default()
+                        .fg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::raw(self.query.clone()),
+                Span::styled(
+                    format!(
+                        "  {}/{}  {}",
+                        self.matches.len(),
+                        self.items.len(),
+                        self.title
+                    ),
+                    dim,
+                ),
+            ])),
+            prompt,
+        );
+        frame.set_cursor_position((prompt.x + 2 + self.query.chars().count() as u16, prompt.y));
+
+        let highlight = Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD);
+        let items: Vec<ListItem> = self
+            .matches
+            .iter()
+            .map(|(i, indices)| {
+                let item = &self.items[*i];
+                let marker = if self.selected.contains(&item.id) {
+                    Span::styled("● ", Style::default().fg(Color::Green))
+                } else {
+                    Span::raw("  ")
+                };
+
+                let mut spans = vec![marker];
+                spans.extend(item.line.chars().enumerate().map(|(n, c)| {
+                    if indices.contains(&n) {
+                        Span::styled(c.to_string(), highlight)
+                    } else {
+                        Span::raw(c.to_string())
+                    }
+                }));
+
+                ListItem::new(Line::from(spans))
+            })
+            .collect();
+
+        frame.render_stateful_widget(
+            List::new(items).highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            list,
+            &mut self.cursor,
+        );
+
+        frame.render_widget(
+            Paragraph::new(format!(
+                "tab select  ctrl-a all  enter confirm  esc cancel  ({} selected)",
+                self.selected.len()
+            ))
+            .style(dim),
+            footer,
+        );
+    }
+}
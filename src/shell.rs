@@ -0,0 +1,224 @@
+use std::cell::RefCell;
+
+use clap::{CommandFactory, Parser};
+use rustyline::{
+    completion::{Completer, Pair},
+    error::ReadlineError,
+    history::FileHistory,
+    CompletionType, Context, Editor, Helper, Highlighter, Hinter, Validator,
+};
+
+use crate::{api::Api, cache, config::Config, utils, Cli, Commands};
+
+const HISTORY_FILE: &str = ".shell_history";
+
+/// Completes the commands, and the tables and ids of the command being typed.
+#[derive(Helper, Hinter, Highlighter, Validator)]
+struct ShellHelper<'a> {
+    api: &'a Api,
+    table: &'a RefCell<Option<String>>,
+}
+
+impl ShellHelper<'_> {
+    fn tables(&self) -> Vec<(String, String)> {
+        cache::table_specs(self.api)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|spec| (spec.name, String::new()))
+            .collect()
+    }
+
+    fn ids(&self, tablename: &str) -> Vec<(String, String)> {
+        self.api
+            .list_table_contents(tablename, None, None)
+            .ok()
+            .and_then(|res| res.get("res").and_then(|v| v.as_array()).cloned())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|task| {
+                let id = task.get("id").and_then(|v| v.as_u64())?;
+                let description = task.get("description").and_then(|v| v.as_str());
+
+                Some((id.to_string(), description.unwrap_or_default().to_string()))
+            })
+            .collect()
+    }
+}
+
+impl Completer for ShellHelper<'_> {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start = line[..pos].rfind(' ').map_or(0, |i| i + 1);
+        let word = &line[start..pos];
+        let previous: Vec<&str> = line[..start].split_whitespace().collect();
+
+        let candidates = match previous.as_slice() {
+            [] => Cli::command()
+                .get_subcommands()
+                .map(|sub| {
+                    let about = sub.get_about().map(|a| a.to_string());
+                    (sub.get_name().to_string(), about.unwrap_or_default())
+                })
+                .chain([
+                    (
+                        "use".to_string(),
+                        "Sets the table of the next commands".to_string(),
+                    ),
+                    ("exit".to_string(), "Leaves the shell".to_string()),
+                ])
+                .collect(),
+            ["use"] => self.tables(),
+            _ if word.starts_with('-') => vec![],
+            [_, rest @ ..] => {
+                let tables = self.tables();
+
+                // the table is the one typed after the command, or the one in use
+                let tablename = rest
+                    .first()
+                    .filter(|name| tables.iter().any(|(table, _)| table == *name))
+                    .map(|name| name.to_string())
+                    .or_else(|| self.table.borrow().clone())
+                    .or_else(|| Config::load().ok()?.default_table);
+
+                let mut candidates = if rest.is_empty() { tables } else { vec![] };
+                if let Some(tablename) = tablename {
+                    candidates.extend(self.ids(&tablename));
+                }
+                candidates
+            }
+        };
+
+        let pairs = candidates
+            .into_iter()
+            .filter(|(candidate, _)| candidate.starts_with(word))
+            .map(|(candidate, help)| Pair {
+                display: if help.is_empty() {
+                    candidate.clone()
+                } else {
+                    format!("{candidate}  ({help})")
+                },
+                replacement: candidate,
+            })
+            .collect();
+
+        Ok((start, pairs))
+    }
+}
+
+/// Reads commands until 'exit' or Ctrl-D, running them with the same `api`.
+pub fn run(api: &Api) -> Result<(), String> {
+    let table = RefCell::new(None);
+
+    // list the candidates like bash does, so the ids come with their description
+    let editor_config = rustyline::Config::builder()
+        .completion_type(CompletionType::List)
+        .auto_add_history(true)
+        .build();
+    let mut editor: Editor<ShellHelper, FileHistory> =
+        Editor::with_config(editor_config).map_err(|e| format!("Internal error: {e}"))?;
+    editor.set_helper(Some(ShellHelper { api, table: &table }));
+
+    let history = utils::data_path(HISTORY_FILE);
+    // there's no history on the first run
+    let _ = editor.load_history(&history);
+
+    println!("Type commands without the 'rsm' prefix, 'help' to list them and 'exit' to leave.");
+    println!("'use <table>' lets you omit the table in the next commands.");
+
+    loop {
+        let prompt = match table.borrow().as_deref() {
+            Some(tablename) => format!("rsm:{tablename}> "),
+            None => "rsm> ".to_string(),
+        };
+
+        let line = match editor.readline(&prompt) {
+            Ok(line) => line,
+            // Ctrl-C only drops the line being typed
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(format!("Internal error: {e}")),
+        };
+
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let Some(words) = shlex::split(line).filter(|words| !words.is_empty()) else {
+            println!("Error: unterminated quote");
+            continue;
+        };
+
+        // reloaded for every line, 'table default' and 'table alias' can change it
+        let config = match Config::load() {
+            Ok(config) => config,
+            Err(e) => {
+                println!("Error: {e}");
+                continue;
+            }
+        };
+
+        match words[0].as_str() {
+            "exit" | "quit" => break,
+            "use" => {
+                match words.get(1..).unwrap_or_default() {
+                    [] => {
+                        table.replace(None);
+                        println!("No table in use, commands need a table again");
+                    }
+                    [tablename] => match cache::table_spec(api, &config.resolve(tablename)) {
+                        Ok(spec) => {
+                            table.replace(Some(spec.name));
+                        }
+                        Err(e) => println!("Error: {e}"),
+                    },
+                    _ => println!("Usage: use [table]"),
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        let mut args = vec!["rsm".to_string()];
+        args.extend(words.iter().cloned());
+
+        let mut cli = match Cli::try_parse_from(args) {
+            Ok(cli) => cli,
+            Err(e) => {
+                // also prints the help and version
+                let _ = e.print();
+                continue;
+            }
+        };
+
+        // the table in use comes before the default one
+        let in_use = table.borrow().clone().or(config.default_table.clone());
+        crate::resolve_tables(&mut cli.command, &config, in_use.as_deref());
+
+        match cli.command {
+            Commands::Login
+            | Commands::Signup
+            | Commands::Logout
+            | Commands::Shell
+            | Commands::Completions(_)
+            | Commands::Man(_) => {
+                println!("Error: '{}' isn't available in the shell", words[0])
+            }
+            _ => {
+                if let Err(e) = crate::execute(api, cli, &config) {
+                    println!("Error: {e}");
+                }
+            }
+        }
+    }
+
+    let _ = editor.save_history(&history);
+
+    Ok(())
+}
@@ -0,0 +1,982 @@
+use chrono::{Datelike, Duration, Months, NaiveDateTime, Timelike};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Table, TableState},
+    DefaultTerminal, Frame,
+};
+use serde_json::Value;
+
+use crate::{
+    api::Api,
+    cache,
+    journal::{self, Operation},
+    utils::{relative_due, Due, TableSpec},
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Focus {
+    Tables,
+    Tasks,
+}
+
+/// What the text being typed is for.
+enum Prompt {
+    AddDescription,
+    AddGroup {
+        description: String,
+        due: Option<Due>,
+    },
+    EditDescription {
+        id: usize,
+    },
+    EditGroup {
+        id: usize,
+    },
+}
+
+impl Prompt {
+    fn title(&self) -> String {
+        match self {
+            Prompt::AddDescription => " New task ".to_string(),
+            Prompt::AddGroup { .. } => " Group (empty for none) ".to_string(),
+            Prompt::EditDescription { id } => format!(" Description of task {id} "),
+            Prompt::EditGroup { id } => format!(" Group of task {id} "),
+        }
+    }
+}
+
+/// What the picked due is for.
+enum DueTarget {
+    Add { description: String },
+    Edit { id: usize },
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum DueField {
+    Date,
+    Hour,
+    Minute,
+}
+
+struct DuePicker {
+    value: NaiveDateTime,
+    field: DueField,
+}
+
+impl DuePicker {
+    /// Starts from `due`, or from the next full hour.
+    fn new(due: Option<NaiveDateTime>) -> Self {
+        let value = due.unwrap_or_else(|| {
+            let now = chrono::Local::now().naive_local() + Duration::hours(1);
+            now.date().and_hms_opt(now.hour(), 0, 0).unwrap_or(now)
+        });
+
+        DuePicker {
+            value,
+            field: DueField::Date,
+        }
+    }
+
+    fn handle(&mut self, code: KeyCode) {
+        let step = match self.field {
+            DueField::Date => Duration::days(1),
+            DueField::Hour => Duration::hours(1),
+            DueField::Minute => Duration::minutes(5),
+        };
+
+        match code {
+            KeyCode::Left | KeyCode::Char('h') => {
+                self.field = match self.field {
+                    DueField::Date | DueField::Hour => DueField::Date,
+                    DueField::Minute => DueField::Hour,
+                }
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                self.field = match self.field {
+                    DueField::Date => DueField::Hour,
+                    DueField::Hour | DueField::Minute => DueField::Minute,
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.value += step,
+            KeyCode::Down | KeyCode::Char('j') => self.value -= step,
+            KeyCode::PageUp => self.value -= Duration::days(7),
+            KeyCode::PageDown => self.value += Duration::days(7),
+            KeyCode::Char('t') => {
+                let today = chrono::Local::now().date_naive();
+                self.value = today.and_time(self.value.time());
+            }
+            _ => {}
+        }
+    }
+
+    /// The month of the picked date as a calendar, followed by the picked time.
+    fn lines(&self) -> Vec<Line<'static>> {
+        let date = self.value.date();
+        let today = chrono::Local::now().date_naive();
+        let first = date.with_day(1).unwrap_or(date);
+        let days_in_month = first
+            .checked_add_months(Months::new(1))
+            .map(|next| (next - first).num_days() as u32)
+            .unwrap_or(31);
+
+        let focused = |field: DueField| {
+            if self.field == field {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default().add_modifier(Modifier::BOLD)
+            }
+        };
+
+        let mut lines = vec![
+            Line::from(date.format("%B %Y").to_string()).centered(),
+            Line::from("Mo Tu We Th Fr Sa Su").style(Style::default().fg(Color::DarkGray)),
+        ];
+
+        let mut week: Vec<Span> = (0..first.weekday().num_days_from_monday())
+            .map(|_| Span::raw("   "))
+            .collect();
+        for day in 1..=days_in_month {
+            let style = if day == date.day() {
+                focused(DueField::Date)
+            } else if first.with_day(day) == Some(today) {
+                Style::default().add_modifier(Modifier::UNDERLINED)
+            } else {
+                Style::default()
+            };
+            week.push(Span::styled(format!("{day:>2}"), style));
+            week.push(Span::raw(" "));
+
+            if week.len() == 14 {
+                lines.push(Line::from(std::mem::take(&mut week)));
+            }
+        }
+        if !week.is_empty() {
+            lines.push(Line::from(week));
+        }
+
+        lines.push(Line::default());
+        lines.push(Line::from(vec![
+            Span::raw("Time "),
+            Span::styled(format!("{:02}", self.value.hour()), focused(DueField::Hour)),
+            Span::raw(":"),
+            Span::styled(
+                format!("{:02}", self.value.minute()),
+                focused(DueField::Minute),
+            ),
+            Span::raw(format!(
+                "  ({})",
+                relative_due(self.value, chrono::Local::now().naive_local())
+            )),
+        ]));
+
+        lines
+    }
+}
+
+enum Mode {
+    Normal,
+    Help,
+    Filter,
+    Input {
+        prompt: Prompt,
+        buffer: String,
+    },
+    PickDue {
+        target: DueTarget,
+        picker: DuePicker,
+    },
+    ConfirmDelete {
+        id: usize,
+    },
+    // destination of a move, as an index in the other tables
+    PickTable {
+        id: usize,
+        selected: usize,
+    },
+}
+
+struct App<'a> {
+    api: &'a Api,
+    specs: Vec<TableSpec>,
+    tables: ListState,
+    tasks: Vec<Value>,
+    selected_task: TableState,
+    focus: Focus,
+    filter: String,
+    mode: Mode,
+    // last message, and whether it's an error
+    status: Option<(String, bool)>,
+    quit: bool,
+}
+
+/// Opens the full-screen interface, every change goes through `api` and reloads the tasks.
+pub fn run(api: &Api) -> Result<(), String> {
+    let mut app = App {
+        api,
+        specs: cache::fetch_table_specs(api)?,
+        tables: ListState::default(),
+        tasks: vec![],
+        selected_task: TableState::default(),
+        focus: Focus::Tables,
+        filter: String::new(),
+        mode: Mode::Normal,
+        status: None,
+        quit: false,
+    };
+    if !app.specs.is_empty() {
+        app.tables.select(Some(0));
+        app.reload();
+    }
+
+    let mut terminal = ratatui::init();
+    let res = app.run(&mut terminal);
+    ratatui::restore();
+
+    res
+}
+
+impl App<'_> {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), String> {
+        while !self.quit {
+            terminal
+                .draw(|frame| self.draw(frame))
+                .map_err(|e| format!("Internal error: {e}"))?;
+
+            if let Event::Key(key) = event::read().map_err(|e| format!("Internal error: {e}"))? {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_key(key);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn spec(&self) -> Option<&TableSpec> {
+        self.tables.selected().and_then(|i| self.specs.get(i))
+    }
+
+    fn tablename(&self) -> String {
+        self.spec()
+            .map(|spec| spec.name.clone())
+            .unwrap_or_default()
+    }
+
+    /// Tasks matching the filter, as typed.
+    fn visible(&self) -> Vec<&Value> {
+        let filter = self.filter.to_lowercase();
+
+        self.tasks
+            .iter()
+            .filter(|task| {
+                ["description", "group"].iter().any(|key| {
+                    task.get(key)
+                        .and_then(|v| v.as_str())
+                        .is_some_and(|v| v.to_lowercase().contains(&filter))
+                })
+            })
+            .collect()
+    }
+
+    fn selected(&self) -> Option<(usize, Value)> {
+        let visible = self.visible();
+        let task = visible.get(self.selected_task.selected()?)?;
+        let id = task.get("id").and_then(|v| v.as_u64())? as usize;
+
+        Some((id, (*task).clone()))
+    }
+
+    fn task(&self, id: usize) -> Option<Value> {
+        self.tasks
+            .iter()
+            .find(|task| task.get("id").and_then(|v| v.as_u64()) == Some(id as u64))
+            .cloned()
+    }
+
+    fn info(&mut self, message: impl Into<String>) {
+        self.status = Some((message.into(), false));
+    }
+
+    fn error(&mut self, message: impl Into<String>) {
+        self.status = Some((message.into(), true));
+    }
+
+    /// Shows the api response, and reloads the tasks on success.
+    fn report(&mut self, res: Result<Value, String>) -> bool {
+        match res {
+            Ok(res) => {
+                let message = res
+                    .get("res")
+                    .map(|v| v.as_str().unwrap_or_default())
+                    .unwrap_or_default()
+                    .to_string();
+                self.info(message);
+                self.reload();
+                true
+            }
+            Err(e) => {
+                self.error(e);
+                false
+            }
+        }
+    }
+
+    fn reload(&mut self) {
+        let tablename = self.tablename();
+
+        match self.api.list_table_contents(&tablename, None, None) {
+            Ok(res) => {
+                self.tasks = res
+                    .get("res")
+                    .and_then(|v| v.as_array())
+                    .cloned()
+                    .unwrap_or_default();
+            }
+            Err(e) => {
+                self.tasks = vec![];
+                self.error(e);
+            }
+        }
+
+        self.clamp_selection();
+    }
+
+    fn refresh_tables(&mut self) {
+        let tablename = self.tablename();
+
+        match cache::fetch_table_specs(self.api) {
+            Ok(specs) => self.specs = specs,
+            Err(e) => return self.error(e),
+        }
+
+        let selected = self.specs.iter().position(|spec| spec.name == tablename);
+        self.tables
+            .select(selected.or((!self.specs.is_empty()).then_some(0)));
+        self.reload();
+    }
+
+    fn clamp_selection(&mut self) {
+        let len = self.visible().len();
+        let selected = match self.selected_task.selected() {
+            _ if len == 0 => None,
+            Some(i) => Some(i.min(len - 1)),
+            None => Some(0),
+        };
+        self.selected_task.select(selected);
+    }
+
+    fn move_selection(&mut self, down: bool) {
+        match self.focus {
+            Focus::Tables => {
+                if self.specs.is_empty() {
+                    return;
+                }
+                let i = self.tables.selected().unwrap_or_default();
+                let i = if down {
+                    (i + 1).min(self.specs.len() - 1)
+                } else {
+                    i.saturating_sub(1)
+                };
+
+                if Some(i) != self.tables.selected() {
+                    self.tables.select(Some(i));
+                    self.filter.clear();
+                    self.selected_task.select(None);
+                    self.reload();
+                }
+            }
+            Focus::Tasks => {
+                if down {
+                    self.selected_task.select_next();
+                } else {
+                    self.selected_task.select_previous();
+                }
+                self.clamp_selection();
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+
+        match std::mem::replace(&mut self.mode, Mode::Normal) {
+            Mode::Normal => self.handle_normal(key.code),
+            // any key closes the help
+            Mode::Help => {}
+            Mode::Filter => match key.code {
+                KeyCode::Char(c) => {
+                    self.filter.push(c);
+                    self.selected_task.select(Some(0));
+                    self.clamp_selection();
+                    self.mode = Mode::Filter;
+                }
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                    self.clamp_selection();
+                    self.mode = Mode::Filter;
+                }
+                KeyCode::Enter => self.focus = Focus::Tasks,
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.clamp_selection();
+                }
+                _ => self.mode = Mode::Filter,
+            },
+            Mode::Input { prompt, mut buffer } => match key.code {
+                KeyCode::Char(c) => {
+                    buffer.push(c);
+                    self.mode = Mode::Input { prompt, buffer };
+                }
+                KeyCode::Backspace => {
+                    buffer.pop();
+                    self.mode = Mode::Input { prompt, buffer };
+                }
+                KeyCode::Enter => self.submit(prompt, buffer.trim().to_string()),
+                KeyCode::Esc => self.info("Cancelled."),
+                _ => self.mode = Mode::Input { prompt, buffer },
+            },
+            Mode::PickDue { target, mut picker } => match key.code {
+                KeyCode::Enter => self.submit_due(target, Some(Due(picker.value))),
+                // a due can't be removed from a task, only left out when adding it
+                KeyCode::Char('n') if matches!(target, DueTarget::Add { .. }) => {
+                    self.submit_due(target, None)
+                }
+                KeyCode::Esc => self.info("Cancelled."),
+                code => {
+                    picker.handle(code);
+                    self.mode = Mode::PickDue { target, picker };
+                }
+            },
+            Mode::ConfirmDelete { id } => match key.code {
+                KeyCode::Char('y') => self.remove(id, false),
+                _ => self.info("Aborted."),
+            },
+            Mode::PickTable { id, mut selected } => {
+                let destinations = self.destinations();
+                match key.code {
+                    KeyCode::Up | KeyCode::Char('k') => selected = selected.saturating_sub(1),
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        selected = (selected + 1).min(destinations.len().saturating_sub(1))
+                    }
+                    KeyCode::Enter => {
+                        if let Some(to) = destinations.get(selected) {
+                            self.move_task(id, to);
+                        }
+                        return;
+                    }
+                    KeyCode::Esc => return self.info("Cancelled."),
+                    _ => {}
+                }
+                self.mode = Mode::PickTable { id, selected };
+            }
+        }
+    }
+
+    fn handle_normal(&mut self, code: KeyCode) {
+        let spec = self.spec().cloned();
+
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Char('?') => self.mode = Mode::Help,
+            KeyCode::Tab | KeyCode::BackTab => {
+                self.focus = match self.focus {
+                    Focus::Tables => Focus::Tasks,
+                    Focus::Tasks => Focus::Tables,
+                }
+            }
+            KeyCode::Left | KeyCode::Char('h') => self.focus = Focus::Tables,
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Enter => self.focus = Focus::Tasks,
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(true),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(false),
+            KeyCode::Char('r') => {
+                self.refresh_tables();
+                self.info("Refreshed.");
+            }
+            KeyCode::Char('/') if spec.is_some() => {
+                self.focus = Focus::Tasks;
+                self.mode = Mode::Filter;
+            }
+            KeyCode::Char('a') if spec.is_some() => {
+                self.mode = Mode::Input {
+                    prompt: Prompt::AddDescription,
+                    buffer: String::new(),
+                }
+            }
+            _ if self.focus != Focus::Tasks => {}
+            KeyCode::Char(c @ ('e' | 'D' | 'g' | 'x' | 'd' | 'm')) => {
+                let (Some(spec), Some((id, task))) = (spec, self.selected()) else {
+                    return;
+                };
+                let field = |key: &str| {
+                    task.get(key)
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default()
+                        .to_string()
+                };
+
+                match c {
+                    'e' => {
+                        self.mode = Mode::Input {
+                            prompt: Prompt::EditDescription { id },
+                            buffer: field("description"),
+                        }
+                    }
+                    'D' if spec.has_due => {
+                        self.mode = Mode::PickDue {
+                            target: DueTarget::Edit { id },
+                            picker: DuePicker::new(
+                                task.get("due").and_then(Due::from_api).map(|d| d.0),
+                            ),
+                        }
+                    }
+                    'g' if spec.has_group => {
+                        self.mode = Mode::Input {
+                            prompt: Prompt::EditGroup { id },
+                            buffer: field("group"),
+                        }
+                    }
+                    'D' | 'g' => self.error(format!(
+                        "The table '{}' doesn't support {}",
+                        spec.name,
+                        if c == 'D' { "dues" } else { "groups" }
+                    )),
+                    'x' => self.remove(id, true),
+                    'd' => self.mode = Mode::ConfirmDelete { id },
+                    'm' if self.destinations().is_empty() => {
+                        self.error("There is no other table to move the task to")
+                    }
+                    'm' => self.mode = Mode::PickTable { id, selected: 0 },
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn submit(&mut self, prompt: Prompt, text: String) {
+        let Some(spec) = self.spec().cloned() else {
+            return;
+        };
+
+        match prompt {
+            Prompt::AddDescription if text.is_empty() => {
+                self.error("The description can't be empty")
+            }
+            Prompt::AddDescription if spec.has_due => {
+                self.mode = Mode::PickDue {
+                    target: DueTarget::Add { description: text },
+                    picker: DuePicker::new(None),
+                }
+            }
+            Prompt::AddDescription => self.submit_due(DueTarget::Add { description: text }, None),
+            Prompt::AddGroup { description, due } => {
+                let group = (!text.is_empty()).then_some(text);
+                self.add(&description, due, group.as_deref());
+            }
+            Prompt::EditDescription { .. } if text.is_empty() => {
+                self.error("The description can't be empty")
+            }
+            Prompt::EditDescription { id } => self.update(id, Some(&text), None, None),
+            Prompt::EditGroup { .. } if text.is_empty() => {
+                self.error("A group can't be removed from a task")
+            }
+            Prompt::EditGroup { id } => self.update(id, None, None, Some(&text)),
+        }
+    }
+
+    fn submit_due(&mut self, target: DueTarget, due: Option<Due>) {
+        let has_group = self.spec().is_some_and(|spec| spec.has_group);
+
+        match target {
+            DueTarget::Add { description } if has_group => {
+                self.mode = Mode::Input {
+                    prompt: Prompt::AddGroup { description, due },
+                    buffer: String::new(),
+                }
+            }
+            DueTarget::Add { description } => self.add(&description, due, None),
+            DueTarget::Edit { id } => self.update(id, None, due, None),
+        }
+    }
+
+    fn add(&mut self, description: &str, due: Option<Due>, group: Option<&str>) {
+        let tablename = self.tablename();
+        let res = self.api.add_task(&tablename, description, due, group);
+
+        // the new task comes last, select it
+        if self.report(res) {
+            self.selected_task
+                .select(Some(self.visible().len().saturating_sub(1)));
+        }
+    }
+
+    fn update(
+        &mut self,
+        id: usize,
+        description: Option<&str>,
+        due: Option<Due>,
+        group: Option<&str>,
+    ) {
+        let tablename = self.tablename();
+        let previous = self.task(id);
+
+        let res = self
+            .api
+            .update_task(&tablename, id, description, due, group);
+        if self.report(res) {
+            self.record(Operation::Update, previous);
+        }
+    }
+
+    /// Removes a task, `done` only changes the message since the backend has no notion of done tasks.
+    fn remove(&mut self, id: usize, done: bool) {
+        let tablename = self.tablename();
+        let previous = self.task(id);
+
+        let res = self.api.remove_task(&tablename, id);
+        if self.report(res) {
+            self.record(Operation::Remove, previous);
+            if done {
+                self.info(format!("Task {id} done"));
+            }
+        }
+    }
+
+    /// Tables a task can be moved to.
+    fn destinations(&self) -> Vec<TableSpec> {
+        let tablename = self.tablename();
+
+        self.specs
+            .iter()
+            .filter(|spec| spec.name != tablename)
+            .cloned()
+            .collect()
+    }
+
+    /// Adds the task to `to`, then removes it from the current table.
+    fn move_task(&mut self, id: usize, to: &TableSpec) {
+        let Some(task) = self.task(id) else {
+            return;
+        };
+        let from = self.tablename();
+
+        // the id the task gets in the destination is needed to undo the move
+        let existing = match journal::task_ids(self.api, &to.name) {
+            Ok(existing) => existing,
+            Err(e) => return self.error(e),
+        };
+
+        // what the destination can't hold is dropped, like 'rsm move' does
+        let res = self.api.add_task(
+            &to.name,
+            task.get("description")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default(),
+            task.get("due")
+                .and_then(Due::from_api)
+                .filter(|_| to.has_due),
+            task.get("group")
+                .and_then(|v| v.as_str())
+                .filter(|_| to.has_group),
+        );
+        if let Err(e) = res {
+            return self.error(format!(
+                "Error when adding task with id {id} to '{}': {e}",
+                to.name
+            ));
+        }
+
+        let res = self.api.remove_task(&from, id);
+        if !self.report(res) {
+            return self.reload();
+        }
+
+        let entry = journal::new_ids(self.api, &to.name, &existing).and_then(|new_ids| {
+            let new_id = new_ids.first().ok_or_else(|| {
+                format!(
+                    "Moved task with id {id}, but its id in '{}' is unknown, it can't be undone",
+                    to.name
+                )
+            })?;
+            Ok(journal::Entry {
+                destination: Some(to.name.clone()),
+                destination_ids: vec![*new_id],
+                ..journal::Entry::new(Operation::Move, &from, vec![task])
+            })
+        });
+        match entry.and_then(journal::record) {
+            Ok(()) => self.info(format!("Moved task with id {id} to '{}'", to.name)),
+            Err(e) => self.error(e),
+        }
+    }
+
+    fn record(&mut self, operation: Operation, task: Option<Value>) {
+        let tablename = self.tablename();
+        let entry = journal::Entry::new(operation, &tablename, task.into_iter().collect());
+
+        if let Err(e) = journal::record(entry) {
+            self.error(e);
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status, footer] = Layout::vertical([
+            Constraint::Min(3),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [sidebar, content] =
+            Layout::horizontal([Constraint::Length(24), Constraint::Min(20)]).areas(main);
+
+        let focused = |focus: Focus| {
+            if self.focus == focus {
+                Style::default().fg(Color::Green)
+            } else {
+                Style::default()
+            }
+        };
+
+        let tables: Vec<ListItem> = self
+            .specs
+            .iter()
+            .map(|spec| {
+                let mut flags = vec![];
+                if spec.has_due {
+                    flags.push("due");
+                }
+                if spec.has_group {
+                    flags.push("group");
+                }
+                ListItem::new(Line::from(vec![
+                    Span::raw(spec.name.clone()),
+                    Span::styled(
+                        format!(" {}", flags.join(",")),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]))
+            })
+            .collect();
+        frame.render_stateful_widget(
+            List::new(tables)
+                .block(
+                    Block::bordered()
+                        .title(" Tables ")
+                        .border_style(focused(Focus::Tables)),
+                )
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            sidebar,
+            &mut self.tables,
+        );
+
+        self.draw_tasks(frame, content, focused(Focus::Tasks));
+
+        if let Some((message, is_error)) = &self.status {
+            let style = if *is_error {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            };
+            frame.render_widget(Paragraph::new(message.as_str()).style(style), status);
+        }
+
+        let hints = match &self.mode {
+            Mode::Normal => "a add  e edit  D due  g group  x done  d delete  m move  / filter  r refresh  ? help  q quit",
+            Mode::Filter => "type to filter  enter keep  esc clear",
+            Mode::Input { .. } => "enter confirm  esc cancel",
+            Mode::PickDue { target: DueTarget::Add { .. }, .. } => {
+                "←/→ field  ↑/↓ change  pgup/pgdn week  t today  enter confirm  n no due  esc cancel"
+            }
+            Mode::PickDue { .. } => {
+                "←/→ field  ↑/↓ change  pgup/pgdn week  t today  enter confirm  esc cancel"
+            }
+            Mode::ConfirmDelete { .. } => "y delete  any other key cancels",
+            Mode::PickTable { .. } => "↑/↓ select  enter move  esc cancel",
+            Mode::Help => "any key closes the help",
+        };
+        frame.render_widget(
+            Paragraph::new(hints).style(Style::default().fg(Color::DarkGray)),
+            footer,
+        );
+
+        self.draw_popup(frame);
+    }
+
+    fn draw_tasks(&mut self, frame: &mut Frame, area: Rect, border_style: Style) {
+        let (has_due, has_group) = self
+            .spec()
+            .map(|spec| (spec.has_due, spec.has_group))
+            .unwrap_or_default();
+        let now = chrono::Local::now().naive_local();
+
+        let mut header = vec!["id"];
+        let mut widths = vec![Constraint::Length(5)];
+        if has_due {
+            header.push("due");
+            widths.push(Constraint::Length(30));
+        }
+        if has_group {
+            header.push("group");
+            widths.push(Constraint::Length(12));
+        }
+        header.push("description");
+        widths.push(Constraint::Min(10));
+
+        let rows: Vec<Row> = self
+            .visible()
+            .into_iter()
+            .map(|task| {
+                let id = task.get("id").and_then(|v| v.as_u64()).unwrap_or_default();
+                let mut cells = vec![Cell::from(id.to_string())];
+                if has_due {
+                    let due = task
+                        .get("due")
+                        .and_then(Due::from_api)
+                        .map(|due| {
+                            format!(
+                                "{} ({})",
+                                due.0.format("%Y-%m-%d %H:%M"),
+                                relative_due(due.0, now)
+                            )
+                        })
+                        .unwrap_or_else(|| "N/A".to_string());
+                    cells.push(Cell::from(due));
+                }
+                if has_group {
+                    let group = task.get("group").and_then(|v| v.as_str());
+                    cells.push(Cell::from(group.unwrap_or("N/A").to_string()));
+                }
+                let description = task.get("description").and_then(|v| v.as_str());
+                cells.push(Cell::from(description.unwrap_or_default().to_string()));
+
+                Row::new(cells)
+            })
+            .collect();
+
+        let mut title = format!(" {} ", self.tablename());
+        if !self.filter.is_empty() || matches!(self.mode, Mode::Filter) {
+            title.push_str(&format!("- filter: {} ", self.filter));
+        }
+
+        frame.render_stateful_widget(
+            Table::new(rows, widths)
+                .header(
+                    Row::new(header).style(
+                        Style::default()
+                            .fg(Color::Green)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                )
+                .block(
+                    Block::bordered()
+                        .title(title.as_str())
+                        .border_style(border_style),
+                )
+                .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+            area,
+            &mut self.selected_task,
+        );
+
+        if matches!(self.mode, Mode::Filter) {
+            let x = area.x + 1 + title.chars().count() as u16 - 1;
+            frame.set_cursor_position((x.min(area.right().saturating_sub(2)), area.y));
+        }
+    }
+
+    fn draw_popup(&self, frame: &mut Frame) {
+        let area = frame.area();
+
+        match &self.mode {
+            Mode::Input { prompt, buffer } => {
+                let popup = popup_area(area, 60, 3);
+                frame.render_widget(Clear, popup);
+                frame.render_widget(
+                    Paragraph::new(buffer.as_str()).block(Block::bordered().title(prompt.title())),
+                    popup,
+                );
+
+                let x = popup.x + 1 + buffer.chars().count() as u16;
+                frame.set_cursor_position((x.min(popup.right().saturating_sub(2)), popup.y + 1));
+            }
+            Mode::PickDue { picker, .. } => {
+                let lines = picker.lines();
+                let popup = popup_area(area, 34, lines.len() as u16 + 2);
+                frame.render_widget(Clear, popup);
+                frame.render_widget(
+                    Paragraph::new(lines).block(Block::bordered().title(" Due ")),
+                    popup,
+                );
+            }
+            Mode::ConfirmDelete { id } => {
+                let popup = popup_area(area, 40, 3);
+                frame.render_widget(Clear, popup);
+                frame.render_widget(
+                    Paragraph::new(format!("Delete task {id}? [y/N]"))
+                        .block(Block::bordered().border_style(Style::default().fg(Color::Red))),
+                    popup,
+                );
+            }
+            Mode::PickTable { id, selected } => {
+                let destinations = self.destinations();
+                let popup = popup_area(area, 30, destinations.len() as u16 + 2);
+                let items: Vec<ListItem> = destinations
+                    .iter()
+                    .map(|spec| ListItem::new(spec.name.clone()))
+                    .collect();
+
+                frame.render_widget(Clear, popup);
+                frame.render_stateful_widget(
+                    List::new(items)
+                        .block(Block::bordered().title(format!(" Move task {id} to ")))
+                        .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+                    popup,
+                    &mut ListState::default().with_selected(Some(*selected)),
+                );
+            }
+            Mode::Help => {
+                let help = [
+                    "tab, h/l    switch between tables and tasks",
+                    "j/k         move the selection",
+                    "a           add a task",
+                    "e           edit the description",
+                    "D           pick the due",
+                    "g           edit the group",
+                    "x           mark as done (removes the task)",
+                    "d           delete",
+                    "m           move to another table",
+                    "/           filter the tasks as you type",
+                    "r           refresh",
+                    "q           quit",
+                    "",
+                    "Removals and updates can be reverted with 'rsm undo'.",
+                ];
+                let popup = popup_area(area, 60, help.len() as u16 + 2);
+                frame.render_widget(Clear, popup);
+                frame.render_widget(
+                    Paragraph::new(help.join("\n")).block(Block::bordered().title(" Keys ")),
+                    popup,
+                );
+            }
+            Mode::Normal | Mode::Filter => {}
+        }
+    }
+}
+
+/// A `width` x `height` area at the center of `area`.
+fn popup_area(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
@@ -1,6 +1,7 @@
 use std::{
     fs,
-    io::{self, Write},
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
 };
 
 pub fn prompt_credentials() -> io::Result<(String, String)> {
@@ -64,6 +65,64 @@ pub fn prompt_logout() -> io::Result<bool> {
     }
 }
 
+pub fn prompt_confirm(question: &str) -> io::Result<bool> {
+    loop {
+        print!("{question} [y/N]: ");
+        io::stdout().flush()?;
+        let mut confirm = String::new();
+        // the end of the input is a no, there's nothing left to ask
+        if io::stdin().read_line(&mut confirm)? == 0 {
+            println!();
+            return Ok(false);
+        }
+
+        match confirm.trim().to_lowercase().as_str() {
+            "y" | "yes" => return Ok(true), // only an explicit yes goes through
+            "n" | "no" | "" => return Ok(false), // 'n' or empty input means no
+            _ => println!("Invalid input. Please respond with 'y' or 'N'."),
+        }
+    }
+}
+
+/// Asks for confirmation before a destructive operation.
+///
+/// The prompt is skipped when `assume_yes` is set (`--yes`), and refused outright
+/// when stdin is not a terminal, so scripts can't hang or destroy data by accident.
+pub fn confirm(question: &str, assume_yes: bool) -> Result<bool, String> {
+    if assume_yes {
+        return Ok(true);
+    }
+
+    if !io::stdin().is_terminal() {
+        return Err(
+            "Refusing to proceed without confirmation, stdin is not a terminal (use '--yes' to skip the prompt)"
+                .to_string(),
+        );
+    }
+
+    prompt_confirm(question).map_err(|e| format!("Internal error: {e}"))
+}
+
+/// Path of a file kept in the project directory, next to the `.token` file.
+pub fn data_path(filename: &str) -> PathBuf {
+    #[cfg(target_os = "linux")]
+    let exe_path = std::env::current_exe().ok().unwrap(); // Get the current executable's path
+
+    #[cfg(target_os = "macos")]
+    let exe_path = std::env::current_exe()
+        .ok()
+        .and_then(|p| fs::canonicalize(p).ok())
+        .unwrap();
+
+    // Go to the parent directory (where the executable is located) from /target/release
+    exe_path
+        .parent()
+        .and_then(|p| p.parent())
+        .and_then(|p| p.parent())
+        .map(|p| p.join(filename))
+        .unwrap()
+}
+
 /// Gets the system timezone as a `chrono_tz::Tz` instance.
 pub fn get_sys_tz() -> Option<chrono_tz::Tz> {
     #[cfg(target_os = "linux")]
@@ -100,6 +159,34 @@ pub fn get_sys_tz() -> Option<chrono_tz::Tz> {
     None
 }
 
+/// Capabilities of a table, as listed by `Api::list_tables_specs`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct TableSpec {
+    pub name: String,
+    pub has_due: bool,
+    pub has_group: bool,
+}
+
+impl TableSpec {
+    pub fn from_json(item: &serde_json::Value) -> Self {
+        TableSpec {
+            name: item
+                .get("name")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            has_due: item
+                .get("has_due")
+                .and_then(|v| v.as_bool())
+                .unwrap_or_default(),
+            has_group: item
+                .get("has_group")
+                .and_then(|v| v.as_bool())
+                .unwrap_or_default(),
+        }
+    }
+}
+
 /// Due parsing logic
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct Due(pub chrono::NaiveDateTime); // due is parsed either as 'hh:mm' of the same day or tomorrow or 'YYYY-MM-dd hh:mm'.
@@ -155,69 +242,428 @@ impl TryFrom<&str> for Due {
     }
 }
 
+impl Due {
+    /// Reads back a due as returned by the api (rfc3339), so it can be sent again.
+    pub fn from_api(value: &serde_json::Value) -> Option<Due> {
+        value
+            .as_str()
+            .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+            .map(|dt| Due(dt.naive_local()))
+    }
+}
+
+/// Describes a due relatively to `now`, e.g. "in 3h", "tomorrow", "yesterday" or "overdue 2d".
+pub fn relative_due(due: chrono::NaiveDateTime, now: chrono::NaiveDateTime) -> String {
+    let diff = due - now;
+    let minutes = diff.num_minutes().abs();
+
+    let amount = if minutes < 60 {
+        format!("{minutes}m")
+    } else if minutes < 60 * 24 {
+        format!("{}h", minutes / 60)
+    } else {
+        format!("{}d", minutes / (60 * 24))
+    };
+
+    if minutes == 0 {
+        "now".to_string()
+    } else if minutes >= 60 * 24 && due.date() == now.date() - chrono::Duration::days(1) {
+        "yesterday".to_string()
+    } else if diff.num_minutes() < 0 {
+        format!("overdue {amount}")
+    } else if minutes >= 60 * 24 && due.date() == now.date() + chrono::Duration::days(1) {
+        "tomorrow".to_string()
+    } else {
+        format!("in {amount}")
+    }
+}
+
 // Custom parser for the `due` field.
 pub fn parse_due(value: &str) -> Result<Due, String> {
     Due::try_from(value).map_err(|e| e.to_string())
 }
 
-// macro to implement from json api response for the formatted types
-#[macro_export]
-macro_rules! impl_table_parsing {
-    ($struct_name:ident { $( $field:ident => $json_key:expr, )* }) => {
-        impl $struct_name {
-            pub fn from_json(item: &serde_json::Value) -> Self {
-                $struct_name {
-                    $(
-                        $field: if $json_key == "due" {
-                            item.get($json_key)
-                                .and_then(|v| v.as_str())
-                                .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
-                                .map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
-                                .unwrap_or("N/A".to_string())
-                        } else {
-                            item.get($json_key)
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("N/A")
-                                .to_string()
-                        },
-                    )*
-                    id: item.get("id").and_then(|v| v.as_i64()).unwrap_or(0) as i32,
-                }
+/// One term of an id selection, e.g. `42`, `10..`, `overdue` or `group:home`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdSelector {
+    Id(usize),
+    /// Bounds are inclusive, a missing one is open
+    Range(Option<usize>, Option<usize>),
+    All,
+    /// The task with the highest id
+    Last,
+    Overdue,
+    Group(String),
+}
+
+impl std::fmt::Display for IdSelector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bound = |b: &Option<usize>| b.map(|b| b.to_string()).unwrap_or_default();
+
+        match self {
+            IdSelector::Id(id) => write!(f, "{id}"),
+            IdSelector::Range(start, end) => write!(f, "{}..{}", bound(start), bound(end)),
+            IdSelector::All => write!(f, "all"),
+            IdSelector::Last => write!(f, "last"),
+            IdSelector::Overdue => write!(f, "overdue"),
+            IdSelector::Group(group) => write!(f, "group:{group}"),
+        }
+    }
+}
+
+impl IdSelector {
+    /// Whether the selector only makes sense against the tasks of the table.
+    fn needs_tasks(&self) -> bool {
+        !matches!(
+            self,
+            IdSelector::Id(_) | IdSelector::Range(Some(_), Some(_))
+        )
+    }
+
+    fn matches(
+        &self,
+        task: &serde_json::Value,
+        last: Option<usize>,
+        now: chrono::NaiveDateTime,
+    ) -> bool {
+        let id = task
+            .get("id")
+            .and_then(|v| v.as_u64())
+            .map(|id| id as usize);
+
+        match self {
+            IdSelector::Id(n) => id == Some(*n),
+            IdSelector::Range(start, end) => id.is_some_and(|id| {
+                start.is_none_or(|start| id >= start) && end.is_none_or(|end| id <= end)
+            }),
+            IdSelector::All => true,
+            IdSelector::Last => id.is_some() && id == last,
+            IdSelector::Overdue => task
+                .get("due")
+                .and_then(Due::from_api)
+                .is_some_and(|due| due.0 < now),
+            IdSelector::Group(group) => {
+                task.get("group").and_then(|v| v.as_str()) == Some(group.as_str())
             }
         }
+    }
+}
+
+/// The ids given on the command line, as selectors to include and to exclude.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IdSelection {
+    pub include: Vec<IdSelector>,
+    pub exclude: Vec<IdSelector>,
+}
+
+impl IdSelection {
+    /// Whether the selection is resolved against the tasks of the table, rather than taken as is.
+    pub fn needs_tasks(&self) -> bool {
+        self.include
+            .iter()
+            .chain(&self.exclude)
+            .any(IdSelector::needs_tasks)
+    }
+}
+
+// longest closed range, so a typo like '1..1000000000' doesn't try every id
+const MAX_RANGE_LEN: usize = 10_000;
+
+/// Parses ids separated by spaces or commas: `42`, `1,2,5`, `10..15`, `10..`, `..5`,
+/// `all`, `last`, `overdue` or `group:<name>`, each one excluded when prefixed with `^`.
+pub fn parse_ids(input: &str) -> Result<IdSelection, String> {
+    let mut selection = IdSelection::default();
+
+    let terms = input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|term| !term.is_empty());
+    for term in terms {
+        match term.strip_prefix('^') {
+            Some(excluded) => selection.exclude.push(parse_selector(excluded)?),
+            None => selection.include.push(parse_selector(term)?),
+        }
+    }
+
+    if selection.include.is_empty() && selection.exclude.is_empty() {
+        return Err("No id given".to_string());
+    }
+
+    Ok(selection)
+}
+
+fn parse_selector(term: &str) -> Result<IdSelector, String> {
+    let id = |value: &str| {
+        value
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid number '{value}' in '{term}'"))
+    };
+
+    match term {
+        "all" => return Ok(IdSelector::All),
+        "last" => return Ok(IdSelector::Last),
+        "overdue" => return Ok(IdSelector::Overdue),
+        _ => {}
+    }
+
+    if let Some(group) = term.strip_prefix("group:") {
+        if group.is_empty() {
+            return Err("Missing group name in 'group:'".to_string());
+        }
+        return Ok(IdSelector::Group(group.to_string()));
+    }
+
+    let Some((start, end)) = term.split_once("..") else {
+        return id(term).map(IdSelector::Id).map_err(|_| {
+            format!(
+                "Invalid id '{term}', expected an id (42), a range (10..15, 10.., ..5), \
+                 all, last, overdue or group:<name>"
+            )
+        });
+    };
+
+    let start = (!start.is_empty()).then(|| id(start)).transpose()?;
+    let end = (!end.is_empty()).then(|| id(end)).transpose()?;
+    match (start, end) {
+        (None, None) => Err("Invalid range '..', give at least one bound".to_string()),
+        (Some(start), Some(end)) if start > end => Err(format!(
+            "Invalid range '{term}', the start is greater than the end"
+        )),
+        (Some(start), Some(end)) if end - start >= MAX_RANGE_LEN => Err(format!(
+            "Invalid range '{term}', it can't span more than {MAX_RANGE_LEN} ids"
+        )),
+        _ => Ok(IdSelector::Range(start, end)),
+    }
+}
+
+/// Resolves the selections to sorted ids, the selectors needing them are matched against `tasks`.
+///
+/// Ids and closed ranges are taken as is, even when the table has no such task,
+/// so the error comes from the api.
+pub fn select_ids(
+    selections: &[IdSelection],
+    tasks: &[serde_json::Value],
+    now: chrono::NaiveDateTime,
+) -> Result<Vec<usize>, String> {
+    let include: Vec<&IdSelector> = selections.iter().flat_map(|s| &s.include).collect();
+    let exclude: Vec<&IdSelector> = selections.iter().flat_map(|s| &s.exclude).collect();
+
+    if include.is_empty() {
+        return Err(
+            "Only exclusions were given, start from 'all' to exclude from every task".to_string(),
+        );
+    }
+
+    let last = tasks
+        .iter()
+        .filter_map(|task| task.get("id").and_then(|v| v.as_u64()))
+        .max()
+        .map(|id| id as usize);
+    let ids_of = |selectors: &[&IdSelector]| -> Vec<usize> {
+        let mut ids = vec![];
+        for selector in selectors {
+            match selector {
+                IdSelector::Id(id) => ids.push(*id),
+                IdSelector::Range(Some(start), Some(end)) => ids.extend(*start..=*end),
+                _ => ids.extend(
+                    tasks
+                        .iter()
+                        .filter(|task| selector.matches(task, last, now))
+                        .filter_map(|task| task.get("id").and_then(|v| v.as_u64()))
+                        .map(|id| id as usize),
+                ),
+            }
+        }
+        ids
     };
+
+    let excluded: std::collections::HashSet<usize> = ids_of(&exclude).into_iter().collect();
+    let mut ids: Vec<usize> = ids_of(&include)
+        .into_iter()
+        .filter(|id| !excluded.contains(id))
+        .collect();
+    ids.sort();
+    ids.dedup();
+
+    if ids.is_empty() {
+        return Err("No task matches the given ids".to_string());
+    }
+
+    Ok(ids)
 }
 
-pub fn parse_ids(input: &str) -> Result<Vec<usize>, String> {
-    use std::str::FromStr;
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use serde_json::json;
+
+    use super::*;
+
+    fn now() -> chrono::NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2026, 10, 19)
+            .and_then(|date| date.and_hms_opt(12, 0, 0))
+            .unwrap()
+    }
+
+    fn selector() -> impl Strategy<Value = IdSelector> {
+        prop_oneof![
+            (0..1000usize).prop_map(IdSelector::Id),
+            (0..1000usize, 0..100usize)
+                .prop_map(|(start, len)| IdSelector::Range(Some(start), Some(start + len))),
+            (0..1000usize).prop_map(|start| IdSelector::Range(Some(start), None)),
+            (0..1000usize).prop_map(|end| IdSelector::Range(None, Some(end))),
+            Just(IdSelector::All),
+            Just(IdSelector::Last),
+            Just(IdSelector::Overdue),
+            "[a-z][a-z0-9_-]{0,8}".prop_map(IdSelector::Group),
+        ]
+    }
+
+    fn selection() -> impl Strategy<Value = IdSelection> {
+        (
+            prop::collection::vec(selector(), 1..6),
+            prop::collection::vec(selector(), 0..4),
+        )
+            .prop_map(|(include, exclude)| IdSelection { include, exclude })
+    }
+
+    fn to_input(selection: &IdSelection, separator: &str) -> String {
+        selection
+            .include
+            .iter()
+            .map(|selector| selector.to_string())
+            .chain(
+                selection
+                    .exclude
+                    .iter()
+                    .map(|selector| format!("^{selector}")),
+            )
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+
+    // tasks with the given ids, every third one overdue and every other one in 'home'
+    fn tasks(ids: &[usize]) -> Vec<serde_json::Value> {
+        ids.iter()
+            .map(|id| {
+                let due = (id % 3 == 0).then_some("2026-10-18T10:00:00+00:00");
+                let group = if id % 2 == 0 { "home" } else { "work" };
+                json!({ "id": id, "description": "task", "due": due, "group": group })
+            })
+            .collect()
+    }
+
+    proptest! {
+        #[test]
+        fn parse_never_panics(input in "\\PC*") {
+            let _ = parse_ids(&input);
+        }
+
+        #[test]
+        fn parse_roundtrips_through_display(selection in selection(), comma in any::<bool>()) {
+            let input = to_input(&selection, if comma { "," } else { " " });
+            prop_assert_eq!(parse_ids(&input), Ok(selection));
+        }
+
+        #[test]
+        fn separators_are_equivalent(selection in selection()) {
+            let spaces = parse_ids(&to_input(&selection, " "));
+            prop_assert_eq!(parse_ids(&to_input(&selection, ",")), spaces.clone());
+            prop_assert_eq!(parse_ids(&to_input(&selection, " , ")), spaces);
+        }
+
+        #[test]
+        fn closed_range_is_inclusive(start in 0..1000usize, len in 0..200usize) {
+            let selection = parse_ids(&format!("{start}..{}", start + len)).unwrap();
+            let ids = select_ids(&[selection], &[], now()).unwrap();
+            prop_assert_eq!(ids, (start..=start + len).collect::<Vec<_>>());
+        }
+
+        #[test]
+        fn reversed_range_is_an_error(start in 1..1000usize, back in 1..1000usize) {
+            let end = start.saturating_sub(back);
+            prop_assume!(end < start);
+            let input = format!("{start}..{end}");
+            prop_assert!(parse_ids(&input).is_err());
+        }
 
-    let mut numbers = Vec::new();
+        #[test]
+        fn selected_ids_are_sorted_unique_and_not_excluded(
+            selections in prop::collection::vec(selection(), 1..3),
+            table in prop::collection::btree_set(0..300usize, 0..40),
+        ) {
+            let table: Vec<usize> = table.into_iter().collect();
+            let tasks = tasks(&table);
 
-    for value in input.split_whitespace() {
-        if value.contains("..") {
-            let parts: Vec<&str> = value.trim().split("..").collect();
-            if parts.len() == 2 {
-                let start = parts[0]
-                    .parse::<usize>()
-                    .map_err(|_| format!("Invalid number: {}", parts[0]))?;
-                let end = parts[1]
-                    .parse::<usize>()
-                    .map_err(|_| format!("Invalid number: {}", parts[1]))?;
-                if start > end {
-                    return Err(format!("Invalid range: {}..{}", start, end));
+            if let Ok(ids) = select_ids(&selections, &tasks, now()) {
+                prop_assert!(!ids.is_empty());
+                prop_assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+
+                // an excluded selector alone never selects one of the resolved ids
+                for selector in selections.iter().flat_map(|s| &s.exclude) {
+                    let alone = IdSelection { include: vec![selector.clone()], exclude: vec![] };
+                    if let Ok(excluded) = select_ids(&[alone], &tasks, now()) {
+                        prop_assert!(ids.iter().all(|id| !excluded.contains(id)));
+                    }
                 }
-                numbers.extend(start..=end);
-            } else {
-                return Err(format!("Invalid range format: {}", value));
             }
-        } else {
-            let num =
-                usize::from_str(value.trim()).map_err(|_| format!("Invalid number: {}", value))?;
-            numbers.push(num);
         }
+
+        #[test]
+        fn table_selectors_only_select_existing_tasks(
+            selector in selector().prop_filter("needs the tasks", IdSelector::needs_tasks),
+            table in prop::collection::btree_set(0..300usize, 1..40),
+        ) {
+            let table: Vec<usize> = table.into_iter().collect();
+            let selection = IdSelection { include: vec![selector], exclude: vec![] };
+
+            if let Ok(ids) = select_ids(&[selection], &tasks(&table), now()) {
+                prop_assert!(ids.iter().all(|id| table.contains(id)));
+            }
+        }
+
+        #[test]
+        fn open_ranges_split_the_table(
+            bound in 0..300usize,
+            table in prop::collection::btree_set(0..300usize, 1..40),
+        ) {
+            let table: Vec<usize> = table.into_iter().collect();
+            let tasks = tasks(&table);
+            let select = |input: &str| {
+                select_ids(&[parse_ids(input).unwrap()], &tasks, now()).unwrap_or_default()
+            };
+
+            let mut ids = select(&format!("..{bound}"));
+            ids.extend(select(&format!("{}..", bound + 1)));
+            prop_assert_eq!(ids, table);
+        }
+    }
+
+    #[test]
+    fn selectors_match_the_tasks() {
+        let tasks = tasks(&[1, 2, 3, 4, 6, 9]);
+        let select = |input: &str| select_ids(&[parse_ids(input).unwrap()], &tasks, now());
+
+        assert_eq!(select("all"), Ok(vec![1, 2, 3, 4, 6, 9]));
+        assert_eq!(select("last"), Ok(vec![9]));
+        assert_eq!(select("overdue"), Ok(vec![3, 6, 9]));
+        assert_eq!(select("group:home"), Ok(vec![2, 4, 6]));
+        assert_eq!(select("all ^overdue ^1"), Ok(vec![2, 4]));
+        assert_eq!(
+            select("1..20 ^7 ^9"),
+            Ok((1..=20).filter(|id| *id != 7 && *id != 9).collect())
+        );
+        assert!(select("group:none").is_err());
     }
 
-    numbers.sort();
-    numbers.dedup();
-    Ok(numbers)
+    #[test]
+    fn invalid_ids_are_errors() {
+        for input in [
+            "", " , ", "x", "1..x", "..", "5..2", "group:", "^", "1...3", "-1", "0..10000",
+        ] {
+            assert!(parse_ids(input).is_err(), "'{input}' should be an error");
+        }
+
+        let only_excluded = parse_ids("^7").unwrap();
+        assert!(select_ids(&[only_excluded], &[], now()).is_err());
+    }
 }